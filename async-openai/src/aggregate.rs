@@ -0,0 +1,480 @@
+//! Merge chunks of streamed responses into the response of the equivalent non streaming request.
+use std::collections::BTreeMap;
+
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    error::OpenAIError,
+    partial_json::parse_partial,
+    types::{
+        ChatChoice, ChatChoiceLogprobs, ChatCompletionMessageToolCall,
+        ChatCompletionResponseMessage, ChatCompletionToolType, Choice, CompletionUsage,
+        CreateChatCompletionResponse, CreateChatCompletionStreamResponse, CreateCompletionResponse,
+        FunctionCall, Logprobs, Role,
+    },
+};
+
+/// Accumulates [CreateChatCompletionStreamResponse] chunks into a [CreateChatCompletionResponse].
+///
+/// Content, tool calls (stitched by their `index`), function call arguments, `finish_reason` and `logprobs`
+/// are tracked separately for every choice, so that requests with `n` greater than 1 are merged too.
+///
+/// ```no_run
+/// use async_openai::{aggregate::ChatCompletionAggregator, types::CreateChatCompletionRequestArgs, Client};
+/// use futures::StreamExt;
+///
+/// # async fn example() -> Result<(), async_openai::error::OpenAIError> {
+/// let request = CreateChatCompletionRequestArgs::default()
+///     .model("gpt-3.5-turbo")
+///     .messages([])
+///     .build()?;
+/// let mut stream = Client::new().chat().create_stream(request).await?;
+///
+/// let mut aggregator = ChatCompletionAggregator::new();
+/// while let Some(chunk) = stream.next().await {
+///     let chunk = chunk?;
+///     aggregator.push(&chunk);
+///     // ... display the chunk as it arrives
+/// }
+/// let response = aggregator.response();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChatCompletionAggregator {
+    id: String,
+    created: u32,
+    model: String,
+    system_fingerprint: Option<String>,
+    usage: Option<CompletionUsage>,
+    choices: BTreeMap<u32, ChatChoiceAggregate>,
+}
+
+#[derive(Debug, Clone)]
+struct ChatChoiceAggregate {
+    choice: ChatChoice,
+    tool_calls: BTreeMap<i32, ChatCompletionMessageToolCall>,
+}
+
+impl ChatCompletionAggregator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Consume `stream` until it ends, and return the aggregated response or the first error of the stream.
+    pub async fn collect<S>(stream: S) -> Result<CreateChatCompletionResponse, OpenAIError>
+    where
+        S: Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>>,
+    {
+        let mut stream = Box::pin(stream);
+        let mut aggregator = Self::new();
+        while let Some(chunk) = stream.next().await {
+            aggregator.push(&chunk?);
+        }
+        Ok(aggregator.response())
+    }
+
+    /// Merge a chunk into the response.
+    #[allow(deprecated)]
+    pub fn push(&mut self, chunk: &CreateChatCompletionStreamResponse) {
+        self.id.clone_from(&chunk.id);
+        self.created = chunk.created;
+        self.model.clone_from(&chunk.model);
+        if chunk.system_fingerprint.is_some() {
+            self.system_fingerprint
+                .clone_from(&chunk.system_fingerprint);
+        }
+        if chunk.usage.is_some() {
+            self.usage.clone_from(&chunk.usage);
+        }
+
+        for choice in chunk.choices.iter() {
+            let aggregate =
+                self.choices
+                    .entry(choice.index)
+                    .or_insert_with(|| ChatChoiceAggregate {
+                        choice: ChatChoice {
+                            index: choice.index,
+                            message: ChatCompletionResponseMessage {
+                                content: None,
+                                tool_calls: None,
+                                role: Role::Assistant,
+                                function_call: None,
+                            },
+                            finish_reason: None,
+                            logprobs: None,
+                        },
+                        tool_calls: BTreeMap::new(),
+                    });
+            let message = &mut aggregate.choice.message;
+            let delta = &choice.delta;
+
+            if let Some(role) = delta.role {
+                message.role = role;
+            }
+            if let Some(content) = &delta.content {
+                message
+                    .content
+                    .get_or_insert_with(String::new)
+                    .push_str(content);
+            }
+
+            for chunk in delta.tool_calls.iter().flatten() {
+                let tool_call = aggregate.tool_calls.entry(chunk.index).or_insert_with(|| {
+                    ChatCompletionMessageToolCall {
+                        id: String::new(),
+                        r#type: ChatCompletionToolType::Function,
+                        function: FunctionCall {
+                            name: String::new(),
+                            arguments: String::new(),
+                        },
+                    }
+                });
+                if let Some(id) = &chunk.id {
+                    tool_call.id.clone_from(id);
+                }
+                if let Some(r#type) = &chunk.r#type {
+                    tool_call.r#type = r#type.clone();
+                }
+                if let Some(function) = &chunk.function {
+                    if let Some(name) = &function.name {
+                        tool_call.function.name.push_str(name);
+                    }
+                    if let Some(arguments) = &function.arguments {
+                        tool_call.function.arguments.push_str(arguments);
+                    }
+                }
+            }
+
+            if let Some(function_call) = &delta.function_call {
+                let call = message.function_call.get_or_insert_with(|| FunctionCall {
+                    name: String::new(),
+                    arguments: String::new(),
+                });
+                if let Some(name) = &function_call.name {
+                    call.name.push_str(name);
+                }
+                if let Some(arguments) = &function_call.arguments {
+                    call.arguments.push_str(arguments);
+                }
+            }
+
+            if choice.finish_reason.is_some() {
+                aggregate.choice.finish_reason = choice.finish_reason;
+            }
+
+            if let Some(logprobs) = &choice.logprobs {
+                let aggregated = aggregate
+                    .choice
+                    .logprobs
+                    .get_or_insert(ChatChoiceLogprobs { content: None });
+                if let Some(content) = &logprobs.content {
+                    aggregated
+                        .content
+                        .get_or_insert_with(Vec::new)
+                        .extend(content.iter().cloned());
+                }
+            }
+        }
+    }
+
+    /// Best-effort snapshot of the arguments of a tool call while they are generated, see [parse_partial].
+    /// Once the choice is finished, [FunctionCall::parse_arguments] parses them strictly.
+    pub fn tool_call_arguments<T: DeserializeOwned>(
+        &self,
+        choice_index: u32,
+        tool_call_index: i32,
+    ) -> Option<T> {
+        let tool_call = self
+            .choices
+            .get(&choice_index)?
+            .tool_calls
+            .get(&tool_call_index)?;
+        serde_json::from_value(parse_partial(&tool_call.function.arguments)?).ok()
+    }
+
+    /// Response aggregated from the chunks pushed so far, with choices ordered by their index.
+    pub fn response(&self) -> CreateChatCompletionResponse {
+        CreateChatCompletionResponse {
+            id: self.id.clone(),
+            choices: self
+                .choices
+                .values()
+                .map(|aggregate| {
+                    let mut choice = aggregate.choice.clone();
+                    if !aggregate.tool_calls.is_empty() {
+                        choice.message.tool_calls =
+                            Some(aggregate.tool_calls.values().cloned().collect());
+                    }
+                    choice
+                })
+                .collect(),
+            created: self.created,
+            model: self.model.clone(),
+            system_fingerprint: self.system_fingerprint.clone(),
+            object: "chat.completion".into(),
+            usage: self.usage.clone(),
+        }
+    }
+}
+
+/// Accumulates streamed [CreateCompletionResponse] chunks of legacy completions into a complete [CreateCompletionResponse].
+///
+/// Text and `logprobs` (tokens, token_logprobs, top_logprobs and text_offset) are concatenated
+/// for every choice, and the last `finish_reason` is kept.
+#[derive(Debug, Clone, Default)]
+pub struct CompletionAggregator {
+    id: String,
+    created: u32,
+    model: String,
+    system_fingerprint: Option<String>,
+    usage: Option<CompletionUsage>,
+    choices: BTreeMap<u32, Choice>,
+}
+
+impl CompletionAggregator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Consume `stream` until it ends, and return the aggregated response or the first error of the stream.
+    pub async fn collect<S>(stream: S) -> Result<CreateCompletionResponse, OpenAIError>
+    where
+        S: Stream<Item = Result<CreateCompletionResponse, OpenAIError>>,
+    {
+        let mut stream = Box::pin(stream);
+        let mut aggregator = Self::new();
+        while let Some(chunk) = stream.next().await {
+            aggregator.push(&chunk?);
+        }
+        Ok(aggregator.response())
+    }
+
+    /// Merge a chunk into the response.
+    pub fn push(&mut self, chunk: &CreateCompletionResponse) {
+        self.id.clone_from(&chunk.id);
+        self.created = chunk.created;
+        self.model.clone_from(&chunk.model);
+        if chunk.system_fingerprint.is_some() {
+            self.system_fingerprint
+                .clone_from(&chunk.system_fingerprint);
+        }
+        if chunk.usage.is_some() {
+            self.usage.clone_from(&chunk.usage);
+        }
+
+        for choice in chunk.choices.iter() {
+            let aggregate = self.choices.entry(choice.index).or_insert_with(|| Choice {
+                text: String::new(),
+                index: choice.index,
+                logprobs: None,
+                finish_reason: None,
+            });
+
+            aggregate.text.push_str(&choice.text);
+
+            if let Some(logprobs) = &choice.logprobs {
+                let aggregated = aggregate.logprobs.get_or_insert(Logprobs {
+                    tokens: vec![],
+                    token_logprobs: vec![],
+                    top_logprobs: vec![],
+                    text_offset: vec![],
+                });
+                aggregated.tokens.extend(logprobs.tokens.iter().cloned());
+                aggregated
+                    .token_logprobs
+                    .extend(logprobs.token_logprobs.iter().cloned());
+                aggregated
+                    .top_logprobs
+                    .extend(logprobs.top_logprobs.iter().cloned());
+                aggregated
+                    .text_offset
+                    .extend(logprobs.text_offset.iter().cloned());
+            }
+
+            if choice.finish_reason.is_some() {
+                aggregate.finish_reason = choice.finish_reason;
+            }
+        }
+    }
+
+    /// Response aggregated from the chunks pushed so far, with choices ordered by their index.
+    pub fn response(&self) -> CreateCompletionResponse {
+        CreateCompletionResponse {
+            id: self.id.clone(),
+            choices: self.choices.values().cloned().collect(),
+            created: self.created,
+            model: self.model.clone(),
+            system_fingerprint: self.system_fingerprint.clone(),
+            object: "text_completion".into(),
+            usage: self.usage.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChatCompletionAggregator, CompletionAggregator};
+    use crate::types::{
+        CompletionFinishReason, CreateChatCompletionStreamResponse, CreateCompletionResponse,
+        FinishReason, Role,
+    };
+
+    fn chunk(choices: serde_json::Value) -> CreateChatCompletionStreamResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion.chunk",
+            "created": 1694268190,
+            "model": "gpt-3.5-turbo-0613",
+            "system_fingerprint": "fp_44709d6fcb",
+            "choices": choices
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_aggregator() {
+        let chunks = vec![
+            chunk(serde_json::json!([
+                {"index": 0, "delta": {"role": "assistant", "content": ""}, "finish_reason": null},
+                {"index": 1, "delta": {"role": "assistant", "tool_calls": [
+                    {"index": 0, "id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": ""}},
+                ]}, "finish_reason": null}
+            ])),
+            chunk(serde_json::json!([
+                {"index": 0, "delta": {"content": "Hello"}, "finish_reason": null,
+                    "logprobs": {"content": [{"token": "Hello", "logprob": -0.1, "bytes": null, "top_logprobs": []}]}},
+                {"index": 1, "delta": {"tool_calls": [
+                    {"index": 0, "function": {"arguments": "{\"location\":"}},
+                    {"index": 1, "id": "call_2", "type": "function", "function": {"name": "get_time", "arguments": "{}"}}
+                ]}, "finish_reason": null}
+            ])),
+            chunk(serde_json::json!([
+                {"index": 1, "delta": {"tool_calls": [{"index": 0, "function": {"arguments": "\"Boston\"}"}}]}, "finish_reason": "tool_calls"},
+                {"index": 0, "delta": {"content": " world"}, "finish_reason": "stop",
+                    "logprobs": {"content": [{"token": " world", "logprob": -0.2, "bytes": null, "top_logprobs": []}]}}
+            ])),
+        ];
+
+        let response =
+            ChatCompletionAggregator::collect(futures::stream::iter(chunks.into_iter().map(Ok)))
+                .await
+                .unwrap();
+
+        assert_eq!(response.object, "chat.completion");
+        assert_eq!(
+            response.system_fingerprint.as_deref(),
+            Some("fp_44709d6fcb")
+        );
+        assert_eq!(response.choices.len(), 2);
+
+        let first = &response.choices[0];
+        assert_eq!(first.message.role, Role::Assistant);
+        assert_eq!(first.message.content.as_deref(), Some("Hello world"));
+        assert_eq!(first.finish_reason, Some(FinishReason::Stop));
+        assert_eq!(
+            first
+                .logprobs
+                .as_ref()
+                .unwrap()
+                .content
+                .as_ref()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(first.message.tool_calls.is_none());
+
+        let second = &response.choices[1];
+        let tool_calls = second.message.tool_calls.as_ref().unwrap();
+        assert_eq!(second.finish_reason, Some(FinishReason::ToolCalls));
+        assert_eq!(tool_calls.len(), 2);
+        assert_eq!(tool_calls[0].id, "call_1");
+        assert_eq!(tool_calls[0].function.name, "get_weather");
+        assert_eq!(
+            tool_calls[0].function.arguments,
+            "{\"location\":\"Boston\"}"
+        );
+        assert_eq!(tool_calls[1].function.name, "get_time");
+    }
+
+    #[test]
+    fn test_tool_call_arguments_snapshot() {
+        let tool_call = |arguments: &str| {
+            chunk(serde_json::json!([{"index": 0, "delta": {"tool_calls": [
+                {"index": 0, "function": {"arguments": arguments}}
+            ]}, "finish_reason": null}]))
+        };
+
+        let mut aggregator = ChatCompletionAggregator::new();
+        aggregator.push(&tool_call("{\"location\": \"Bos"));
+        assert_eq!(
+            aggregator.tool_call_arguments::<serde_json::Value>(0, 0),
+            Some(serde_json::json!({"location": "Bos"}))
+        );
+        assert_eq!(
+            aggregator.tool_call_arguments::<serde_json::Value>(0, 1),
+            None
+        );
+
+        aggregator.push(&tool_call("ton\"}"));
+        let arguments: serde_json::Value = aggregator.response().choices[0]
+            .message
+            .tool_calls
+            .as_ref()
+            .unwrap()[0]
+            .function
+            .parse_arguments()
+            .unwrap();
+        assert_eq!(arguments, serde_json::json!({"location": "Boston"}));
+    }
+
+    #[test]
+    fn test_completion_aggregator() {
+        let chunk = |choices: serde_json::Value| -> CreateCompletionResponse {
+            serde_json::from_value(serde_json::json!({
+                "id": "cmpl-123",
+                "object": "text_completion",
+                "created": 1694268190,
+                "model": "gpt-3.5-turbo-instruct",
+                "system_fingerprint": null,
+                "choices": choices
+            }))
+            .unwrap()
+        };
+
+        let mut aggregator = CompletionAggregator::new();
+        aggregator.push(&chunk(serde_json::json!([
+            {"text": "Hello", "index": 0, "finish_reason": null, "logprobs": {
+                "tokens": ["Hello"], "token_logprobs": [-0.1], "top_logprobs": [{"Hello": -0.1}], "text_offset": [10]
+            }},
+            {"text": "Hi", "index": 1, "finish_reason": null, "logprobs": null}
+        ])));
+        aggregator.push(&chunk(serde_json::json!([
+            {"text": " world", "index": 0, "finish_reason": "length", "logprobs": {
+                "tokens": [" world"], "token_logprobs": [null], "top_logprobs": [{" world": -0.3}], "text_offset": [15]
+            }},
+            {"text": "!", "index": 1, "finish_reason": "stop", "logprobs": null}
+        ])));
+
+        let response = aggregator.response();
+        assert_eq!(response.object, "text_completion");
+        assert_eq!(response.choices.len(), 2);
+
+        let first = &response.choices[0];
+        assert_eq!(first.text, "Hello world");
+        assert_eq!(first.finish_reason, Some(CompletionFinishReason::Length));
+        let logprobs = first.logprobs.as_ref().unwrap();
+        assert_eq!(logprobs.tokens, vec!["Hello", " world"]);
+        assert_eq!(logprobs.token_logprobs, vec![Some(-0.1), None]);
+        assert_eq!(logprobs.top_logprobs.len(), 2);
+        assert_eq!(logprobs.text_offset, vec![10, 15]);
+
+        assert_eq!(response.choices[1].text, "Hi!");
+        assert_eq!(
+            response.choices[1].finish_reason,
+            Some(CompletionFinishReason::Stop)
+        );
+    }
+}
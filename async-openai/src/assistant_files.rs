@@ -1,11 +1,13 @@
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
     config::Config,
     error::OpenAIError,
+    pagination::paginate,
     types::{
         AssistantFileObject, CreateAssistantFileRequest, DeleteAssistantFileResponse,
-        ListAssistantFilesResponse,
+        ListAssistantFilesQuery, ListAssistantFilesResponse,
     },
     Client,
 };
@@ -55,6 +57,8 @@ impl<'c, C: Config> AssistantFiles<'c, C> {
     }
 
     /// Returns a list of assistant files.
+    ///
+    /// `query` is usually a [ListAssistantFilesQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list<Q>(&self, query: &Q) -> Result<ListAssistantFilesResponse, OpenAIError>
     where
         Q: Serialize + ?Sized,
@@ -63,4 +67,24 @@ impl<'c, C: Config> AssistantFiles<'c, C> {
             .get_with_query(&format!("/assistants/{}/files", self.assistant_id), query)
             .await
     }
+
+    /// Same as [AssistantFiles::list], only taking a [ListAssistantFilesQuery] so that mistakes in the query don't compile.
+    pub async fn list_typed(
+        &self,
+        query: &ListAssistantFilesQuery,
+    ) -> Result<ListAssistantFilesResponse, OpenAIError> {
+        self.list(query).await
+    }
+
+    /// Returns all files of the assistant, fetching pages of `limit` objects as the stream is polled.
+    pub fn list_all(
+        &self,
+        query: ListAssistantFilesQuery,
+    ) -> impl Stream<Item = Result<AssistantFileObject, OpenAIError>> + 'c {
+        paginate::<C, ListAssistantFilesResponse>(
+            self.client,
+            format!("/assistants/{}/files", self.assistant_id),
+            query,
+        )
+    }
 }
@@ -1,11 +1,13 @@
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
     config::Config,
     error::OpenAIError,
+    pagination::paginate,
     types::{
-        AssistantObject, CreateAssistantRequest, DeleteAssistantResponse, ListAssistantsResponse,
-        ModifyAssistantRequest,
+        AssistantObject, CreateAssistantRequest, DeleteAssistantResponse, ListAssistantsQuery,
+        ListAssistantsResponse, ModifyAssistantRequest,
     },
     AssistantFiles, Client,
 };
@@ -32,6 +34,7 @@ impl<'c, C: Config> Assistants<'c, C> {
         &self,
         request: CreateAssistantRequest,
     ) -> Result<AssistantObject, OpenAIError> {
+        self.client.validate(&request)?;
         self.client.post("/assistants", request).await
     }
 
@@ -48,6 +51,7 @@ impl<'c, C: Config> Assistants<'c, C> {
         assistant_id: &str,
         request: ModifyAssistantRequest,
     ) -> Result<AssistantObject, OpenAIError> {
+        self.client.validate(&request)?;
         self.client
             .post(&format!("/assistants/{assistant_id}"), request)
             .await
@@ -61,10 +65,28 @@ impl<'c, C: Config> Assistants<'c, C> {
     }
 
     /// Returns a list of assistants.
+    ///
+    /// `query` is usually a [ListAssistantsQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list<Q>(&self, query: &Q) -> Result<ListAssistantsResponse, OpenAIError>
     where
         Q: Serialize + ?Sized,
     {
         self.client.get_with_query("/assistants", query).await
     }
+
+    /// Same as [Assistants::list], only taking a [ListAssistantsQuery] so that mistakes in the query don't compile.
+    pub async fn list_typed(
+        &self,
+        query: &ListAssistantsQuery,
+    ) -> Result<ListAssistantsResponse, OpenAIError> {
+        self.list(query).await
+    }
+
+    /// Returns all assistants, fetching pages of `limit` objects as the stream is polled.
+    pub fn list_all(
+        &self,
+        query: ListAssistantsQuery,
+    ) -> impl Stream<Item = Result<AssistantObject, OpenAIError>> + 'c {
+        paginate::<C, ListAssistantsResponse>(self.client, "/assistants".to_string(), query)
+    }
 }
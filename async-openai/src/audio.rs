@@ -5,6 +5,7 @@ use crate::{
         CreateSpeechRequest, CreateSpeechResponse, CreateTranscriptionRequest,
         CreateTranscriptionResponse, CreateTranslationRequest, CreateTranslationResponse,
     },
+    usage::UsageTotals,
     Client,
 };
 
@@ -24,9 +25,14 @@ impl<'c, C: Config> Audio<'c, C> {
         &self,
         request: CreateTranscriptionRequest,
     ) -> Result<CreateTranscriptionResponse, OpenAIError> {
-        self.client
+        self.client.validate(&request)?;
+        let model = request.model.clone();
+        let response: CreateTranscriptionResponse = self
+            .client
             .post_form("/audio/transcriptions", request)
-            .await
+            .await?;
+        self.record_usage(&model, response.duration);
+        Ok(response)
     }
 
     /// Translates audio into into English.
@@ -34,7 +40,14 @@ impl<'c, C: Config> Audio<'c, C> {
         &self,
         request: CreateTranslationRequest,
     ) -> Result<CreateTranslationResponse, OpenAIError> {
-        self.client.post_form("/audio/translations", request).await
+        self.client.validate(&request)?;
+        let model = request.model.clone();
+        let response: CreateTranslationResponse = self
+            .client
+            .post_form("/audio/translations", request)
+            .await?;
+        self.record_usage(&model, response.duration);
+        Ok(response)
     }
 
     /// Generates audio from the input text.
@@ -42,8 +55,33 @@ impl<'c, C: Config> Audio<'c, C> {
         &self,
         request: CreateSpeechRequest,
     ) -> Result<CreateSpeechResponse, OpenAIError> {
+        self.client.validate(&request)?;
+        let (model, characters) = (request.model.to_string(), request.input.chars().count());
         let bytes = self.client.post_raw("/audio/speech", request).await?;
+        self.client.record_usage(
+            "audio",
+            &model,
+            None,
+            UsageTotals {
+                characters: characters as u64,
+                ..Default::default()
+            },
+        );
 
         Ok(CreateSpeechResponse { bytes })
     }
+
+    /// Record the `duration` of transcribed or translated audio, which is only reported in the `verbose_json` format.
+    fn record_usage(&self, model: &str, duration: Option<f32>) {
+        match duration {
+            Some(duration) => {
+                let usage = UsageTotals {
+                    seconds: duration as f64,
+                    ..Default::default()
+                };
+                self.client.record_usage("audio", model, None, usage);
+            }
+            None => self.client.record_request("audio", model, None),
+        }
+    }
 }
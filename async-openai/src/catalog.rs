@@ -0,0 +1,904 @@
+//! Limits, features and prices of [models](https://platform.openai.com/docs/models), see [ModelCatalog].
+use std::collections::HashMap;
+
+use crate::{
+    error::OpenAIError,
+    types::{
+        ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPart,
+        ChatCompletionRequestUserMessageContent, ChatCompletionResponseFormatType, CompletionUsage,
+        CreateChatCompletionRequest, CreateEmbeddingRequest, CreateImageEditRequest,
+        CreateImageRequest, CreateImageVariationRequest, ImageQuality, ImageSize,
+    },
+};
+
+/// Features supported by a model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModelFeatures {
+    /// Tool and function calling.
+    pub tools: bool,
+    /// Image inputs in user messages.
+    pub vision: bool,
+    /// `json_object` response format.
+    pub json_mode: bool,
+    /// Log probabilities of output tokens.
+    pub logprobs: bool,
+    /// Number of dimensions of the embeddings of embedding models.
+    pub embedding_dimensions: Option<u32>,
+    /// Whether fewer embedding dimensions can be requested with [CreateEmbeddingRequest::dimensions].
+    pub adjustable_dimensions: bool,
+    /// Maximum number of images generated by a request to an image model.
+    pub max_images: Option<u8>,
+    /// Maximum number of characters of the prompt of an image request.
+    pub max_prompt_chars: Option<usize>,
+    /// `style` of generated images.
+    pub image_styles: bool,
+    /// Image edits and variations.
+    pub image_edits: bool,
+}
+
+/// Price in USD of an image of `size` in `quality`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePrice {
+    pub size: ImageSize,
+    pub quality: ImageQuality,
+    pub per_image: f64,
+}
+
+/// Price of a model in USD.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelPricing {
+    /// Price of a million input (prompt) and output (completion) tokens.
+    Tokens { input: f64, output: f64 },
+    /// Price of an image by size and quality, sizes and qualities without a price are not supported by the model.
+    Image { prices: Vec<ImagePrice> },
+    /// Price of a million characters of input text.
+    Characters { per_million: f64 },
+    /// Price of a minute of audio, rounded to the nearest second.
+    Minutes { per_minute: f64 },
+}
+
+impl ModelPricing {
+    /// Cost in USD of the tokens of a request, `None` for models which are not priced by tokens.
+    pub fn tokens_cost(&self, prompt_tokens: u32, completion_tokens: u32) -> Option<f64> {
+        match self {
+            Self::Tokens { input, output } => Some(
+                (prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1_000_000.0,
+            ),
+            _ => None,
+        }
+    }
+
+    /// Price in USD of an image of `size` in `quality`, `None` for models which are not priced by images
+    /// or don't support them.
+    pub fn image_price(&self, size: ImageSize, quality: &ImageQuality) -> Option<f64> {
+        match self {
+            Self::Image { prices } => prices
+                .iter()
+                .find(|price| price.size == size && &price.quality == quality)
+                .map(|price| price.per_image),
+            _ => None,
+        }
+    }
+}
+
+/// Limits, features and price of a model.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelInfo {
+    /// Maximum number of tokens of the prompt and the completion together.
+    pub context_window: Option<u32>,
+    /// Maximum number of tokens of a completion.
+    pub max_output_tokens: Option<u32>,
+    pub features: ModelFeatures,
+    pub pricing: Option<ModelPricing>,
+}
+
+impl ModelInfo {
+    /// Cost in USD of the usage reported for a request, `None` for models which are not priced by tokens.
+    pub fn cost(&self, usage: &CompletionUsage) -> Option<f64> {
+        self.pricing
+            .as_ref()?
+            .tokens_cost(usage.prompt_tokens, usage.completion_tokens)
+    }
+}
+
+/// Catalog of [ModelInfo] by model id.
+///
+/// [Default] is the built-in catalog of OpenAI models, entries can be added or overridden with [ModelCatalog::with_model],
+/// for example for fine-tuned models, new models or negotiated prices.
+/// Dated snapshots (`gpt-4-0613`) and fine-tuned models (`ft:gpt-3.5-turbo-0125:org::id`)
+/// resolve to the entry of their base model when they don't have one.
+///
+/// ```
+/// use async_openai::catalog::{ModelCatalog, ModelInfo, ModelPricing};
+///
+/// let catalog = ModelCatalog::default().with_model(
+///     "my-deployment",
+///     ModelInfo {
+///         context_window: Some(16385),
+///         pricing: Some(ModelPricing::Tokens { input: 0.5, output: 1.5 }),
+///         ..Default::default()
+///     },
+/// );
+///
+/// assert_eq!(catalog.get("gpt-4-0613").unwrap().context_window, Some(8192));
+/// assert!(catalog.get("my-deployment").is_some());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelCatalog {
+    models: HashMap<String, ModelInfo>,
+}
+
+impl Default for ModelCatalog {
+    fn default() -> Self {
+        let mut catalog = Self::empty();
+        for (id, context_window, max_output_tokens, features, pricing) in builtin_models() {
+            catalog.models.insert(
+                id.to_string(),
+                ModelInfo {
+                    context_window,
+                    max_output_tokens,
+                    features,
+                    pricing: Some(pricing),
+                },
+            );
+        }
+        catalog
+    }
+}
+
+impl ModelCatalog {
+    /// Catalog without any model.
+    pub fn empty() -> Self {
+        Self {
+            models: HashMap::new(),
+        }
+    }
+
+    /// Add or replace the entry of `model`.
+    pub fn with_model<S: Into<String>>(mut self, model: S, info: ModelInfo) -> Self {
+        self.models.insert(model.into(), info);
+        self
+    }
+
+    /// Entry of `model`, or of its base model.
+    pub fn get(&self, model: &str) -> Option<&ModelInfo> {
+        if let Some(info) = self.models.get(model) {
+            return Some(info);
+        }
+
+        // ft:gpt-3.5-turbo-0125:org:suffix:id
+        let model = match model.strip_prefix("ft:") {
+            Some(fine_tuned) => fine_tuned.split(':').next().unwrap_or(fine_tuned),
+            None => model,
+        };
+        if let Some(info) = self.models.get(model) {
+            return Some(info);
+        }
+
+        // Model id of a dated snapshot, like gpt-4-32k of gpt-4-32k-0613 or gpt-4o of gpt-4o-2024-05-13
+        self.models
+            .iter()
+            .find(|(id, _)| {
+                model
+                    .strip_prefix(id.as_str())
+                    .and_then(|rest| rest.strip_prefix('-'))
+                    .is_some_and(is_snapshot_date)
+            })
+            .map(|(_, info)| info)
+    }
+
+    /// Model ids of the catalog.
+    pub fn models(&self) -> impl Iterator<Item = &str> {
+        self.models.keys().map(String::as_str)
+    }
+
+    /// Cost in USD of the usage reported for a request to `model`.
+    pub fn cost(&self, model: &str, usage: &CompletionUsage) -> Option<f64> {
+        self.get(model)?.cost(usage)
+    }
+
+    /// Check that `request` only uses features supported by its model, and fits its output limit.
+    /// Requests to models which are not in the catalog are not checked.
+    #[allow(deprecated)]
+    pub fn check_chat_request(
+        &self,
+        request: &CreateChatCompletionRequest,
+    ) -> Result<(), OpenAIError> {
+        let Some(info) = self.get(&request.model) else {
+            return Ok(());
+        };
+        let unsupported = |feature: &str| {
+            Err(OpenAIError::InvalidArgument(format!(
+                "{} doesn't support {feature}",
+                request.model
+            )))
+        };
+
+        if let (Some(max_tokens), Some(limit)) = (request.max_tokens, info.max_output_tokens) {
+            if max_tokens as u32 > limit {
+                return Err(OpenAIError::InvalidArgument(format!(
+                    "max_tokens of {max_tokens} is over the output limit of {limit} tokens of {}",
+                    request.model
+                )));
+            }
+        }
+        if !info.features.tools && (request.tools.is_some() || request.functions.is_some()) {
+            return unsupported("tools");
+        }
+        if !info.features.json_mode
+            && request
+                .response_format
+                .as_ref()
+                .is_some_and(|format| format.r#type == ChatCompletionResponseFormatType::JsonObject)
+        {
+            return unsupported("JSON mode");
+        }
+        if !info.features.logprobs && request.logprobs == Some(true) {
+            return unsupported("logprobs");
+        }
+        if !info.features.vision && request.messages.iter().any(has_image) {
+            return unsupported("image inputs");
+        }
+
+        Ok(())
+    }
+
+    /// Check that the requested dimensions are supported by the embedding model.
+    pub fn check_embedding_request(
+        &self,
+        request: &CreateEmbeddingRequest,
+    ) -> Result<(), OpenAIError> {
+        let (Some(info), Some(dimensions)) = (self.get(&request.model), request.dimensions) else {
+            return Ok(());
+        };
+
+        match info.features.embedding_dimensions {
+            Some(max) if info.features.adjustable_dimensions && dimensions <= max => Ok(()),
+            Some(max) if dimensions == max => Ok(()),
+            _ => Err(OpenAIError::InvalidArgument(format!(
+                "{} doesn't support {dimensions} dimensions",
+                request.model
+            ))),
+        }
+    }
+
+    /// Check that the number, prompt, style, size and quality of the requested images are supported by the image model.
+    /// Sizes and qualities are supported when they have a price, they are not checked for models which are not priced by images.
+    /// Requests to models which are not in the catalog are not checked.
+    pub fn check_image_request(&self, request: &CreateImageRequest) -> Result<(), OpenAIError> {
+        let model = request.model.clone().unwrap_or_default().to_string();
+        let Some(info) = self.get(&model) else {
+            return Ok(());
+        };
+
+        if !info.features.image_styles && request.style.is_some() {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{model} doesn't support image styles"
+            )));
+        }
+        check_images(
+            &model,
+            info,
+            request.n,
+            Some(&request.prompt),
+            request.size.unwrap_or_default(),
+            request.quality.clone().unwrap_or_default(),
+        )
+    }
+
+    /// Check that image edits are supported by the image model, like [ModelCatalog::check_image_request].
+    pub fn check_image_edit_request(
+        &self,
+        request: &CreateImageEditRequest,
+    ) -> Result<(), OpenAIError> {
+        let model = request.model.clone().unwrap_or_default().to_string();
+        let Some(info) = self.get(&model) else {
+            return Ok(());
+        };
+
+        if !info.features.image_edits {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{model} doesn't support image edits"
+            )));
+        }
+        check_images(
+            &model,
+            info,
+            request.n,
+            Some(&request.prompt),
+            request.size.map(ImageSize::from).unwrap_or_default(),
+            ImageQuality::Standard,
+        )
+    }
+
+    /// Check that image variations are supported by the image model, like [ModelCatalog::check_image_request].
+    pub fn check_image_variation_request(
+        &self,
+        request: &CreateImageVariationRequest,
+    ) -> Result<(), OpenAIError> {
+        let model = request.model.clone().unwrap_or_default().to_string();
+        let Some(info) = self.get(&model) else {
+            return Ok(());
+        };
+
+        if !info.features.image_edits {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{model} doesn't support image variations"
+            )));
+        }
+        check_images(
+            &model,
+            info,
+            request.n,
+            None,
+            request.size.map(ImageSize::from).unwrap_or_default(),
+            ImageQuality::Standard,
+        )
+    }
+}
+
+/// Check the number, prompt, size and quality of the images requested from `model`.
+fn check_images(
+    model: &str,
+    info: &ModelInfo,
+    n: Option<u8>,
+    prompt: Option<&str>,
+    size: ImageSize,
+    quality: ImageQuality,
+) -> Result<(), OpenAIError> {
+    if let (Some(n), Some(max)) = (n, info.features.max_images) {
+        if n > max {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{model} supports at most {max} images per request, got {n}"
+            )));
+        }
+    }
+    if let (Some(prompt), Some(max)) = (prompt, info.features.max_prompt_chars) {
+        let length = prompt.chars().count();
+        if length > max {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "prompt of {length} characters is over the limit of {max} characters of {model}"
+            )));
+        }
+    }
+    if let Some(pricing @ ModelPricing::Image { .. }) = &info.pricing {
+        if pricing.image_price(size, &quality).is_none() {
+            return Err(OpenAIError::InvalidArgument(format!(
+                "{model} doesn't support {size} images in {} quality",
+                match quality {
+                    ImageQuality::Standard => "standard",
+                    ImageQuality::HD => "hd",
+                }
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// `0613` or `2024-04-09`.
+fn is_snapshot_date(date: &str) -> bool {
+    let digits =
+        |part: &str, len: usize| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    let parts: Vec<&str> = date.split('-').collect();
+    match parts[..] {
+        [mmdd] => digits(mmdd, 4),
+        [year, month, day] => digits(year, 4) && digits(month, 2) && digits(day, 2),
+        _ => false,
+    }
+}
+
+fn has_image(message: &ChatCompletionRequestMessage) -> bool {
+    match message {
+        ChatCompletionRequestMessage::User(message) => match &message.content {
+            ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                .iter()
+                .any(|part| matches!(part, ChatCompletionRequestMessageContentPart::Image(_))),
+            ChatCompletionRequestUserMessageContent::Text(_) => false,
+        },
+        _ => false,
+    }
+}
+
+type BuiltinModel = (
+    &'static str,
+    Option<u32>,
+    Option<u32>,
+    ModelFeatures,
+    ModelPricing,
+);
+
+/// Published limits and prices of OpenAI models.
+fn builtin_models() -> Vec<BuiltinModel> {
+    let chat = ModelFeatures {
+        tools: true,
+        logprobs: true,
+        ..Default::default()
+    };
+    let chat_json = ModelFeatures {
+        json_mode: true,
+        ..chat
+    };
+    let vision = ModelFeatures {
+        vision: true,
+        ..Default::default()
+    };
+    let multimodal = ModelFeatures {
+        vision: true,
+        ..chat_json
+    };
+    let completion = ModelFeatures {
+        logprobs: true,
+        ..Default::default()
+    };
+    let embedding = |dimensions, adjustable_dimensions| ModelFeatures {
+        embedding_dimensions: Some(dimensions),
+        adjustable_dimensions,
+        ..Default::default()
+    };
+    let dall_e_3 = ModelFeatures {
+        max_images: Some(1),
+        max_prompt_chars: Some(4000),
+        image_styles: true,
+        ..Default::default()
+    };
+    let dall_e_2 = ModelFeatures {
+        max_images: Some(10),
+        max_prompt_chars: Some(1000),
+        image_edits: true,
+        ..Default::default()
+    };
+    let tokens = |input, output| ModelPricing::Tokens { input, output };
+    let images = |prices: &[(ImageSize, ImageQuality, f64)]| ModelPricing::Image {
+        prices: prices
+            .iter()
+            .map(|(size, quality, per_image)| ImagePrice {
+                size: *size,
+                quality: quality.clone(),
+                per_image: *per_image,
+            })
+            .collect(),
+    };
+    let (standard, hd) = (ImageQuality::Standard, ImageQuality::HD);
+
+    vec![
+        (
+            "gpt-4o",
+            Some(128_000),
+            Some(4096),
+            multimodal,
+            tokens(5.0, 15.0),
+        ),
+        (
+            "gpt-4-turbo",
+            Some(128_000),
+            Some(4096),
+            multimodal,
+            tokens(10.0, 30.0),
+        ),
+        (
+            "gpt-4-turbo-preview",
+            Some(128_000),
+            Some(4096),
+            chat_json,
+            tokens(10.0, 30.0),
+        ),
+        (
+            "gpt-4-0125-preview",
+            Some(128_000),
+            Some(4096),
+            chat_json,
+            tokens(10.0, 30.0),
+        ),
+        (
+            "gpt-4-1106-preview",
+            Some(128_000),
+            Some(4096),
+            chat_json,
+            tokens(10.0, 30.0),
+        ),
+        (
+            "gpt-4-vision-preview",
+            Some(128_000),
+            Some(4096),
+            vision,
+            tokens(10.0, 30.0),
+        ),
+        (
+            "gpt-4-1106-vision-preview",
+            Some(128_000),
+            Some(4096),
+            vision,
+            tokens(10.0, 30.0),
+        ),
+        ("gpt-4", Some(8192), Some(8192), chat, tokens(30.0, 60.0)),
+        (
+            "gpt-4-32k",
+            Some(32_768),
+            Some(32_768),
+            chat,
+            tokens(60.0, 120.0),
+        ),
+        (
+            "gpt-3.5-turbo",
+            Some(16_385),
+            Some(4096),
+            chat_json,
+            tokens(0.5, 1.5),
+        ),
+        (
+            "gpt-3.5-turbo-0125",
+            Some(16_385),
+            Some(4096),
+            chat_json,
+            tokens(0.5, 1.5),
+        ),
+        (
+            "gpt-3.5-turbo-1106",
+            Some(16_385),
+            Some(4096),
+            chat_json,
+            tokens(1.0, 2.0),
+        ),
+        (
+            "gpt-3.5-turbo-0613",
+            Some(4096),
+            Some(4096),
+            chat,
+            tokens(1.5, 2.0),
+        ),
+        (
+            "gpt-3.5-turbo-16k",
+            Some(16_385),
+            Some(16_385),
+            chat,
+            tokens(3.0, 4.0),
+        ),
+        (
+            "gpt-3.5-turbo-instruct",
+            Some(4096),
+            Some(4096),
+            completion,
+            tokens(1.5, 2.0),
+        ),
+        (
+            "davinci-002",
+            Some(16_384),
+            Some(16_384),
+            completion,
+            tokens(2.0, 2.0),
+        ),
+        (
+            "babbage-002",
+            Some(16_384),
+            Some(16_384),
+            completion,
+            tokens(0.4, 0.4),
+        ),
+        (
+            "text-embedding-3-small",
+            Some(8191),
+            None,
+            embedding(1536, true),
+            tokens(0.02, 0.0),
+        ),
+        (
+            "text-embedding-3-large",
+            Some(8191),
+            None,
+            embedding(3072, true),
+            tokens(0.13, 0.0),
+        ),
+        (
+            "text-embedding-ada-002",
+            Some(8191),
+            None,
+            embedding(1536, false),
+            tokens(0.1, 0.0),
+        ),
+        (
+            "text-moderation-latest",
+            Some(32_768),
+            None,
+            Default::default(),
+            tokens(0.0, 0.0),
+        ),
+        (
+            "text-moderation-stable",
+            Some(32_768),
+            None,
+            Default::default(),
+            tokens(0.0, 0.0),
+        ),
+        (
+            "dall-e-3",
+            None,
+            None,
+            dall_e_3,
+            images(&[
+                (ImageSize::S1024x1024, standard.clone(), 0.04),
+                (ImageSize::S1792x1024, standard.clone(), 0.08),
+                (ImageSize::S1024x1792, standard.clone(), 0.08),
+                (ImageSize::S1024x1024, hd.clone(), 0.08),
+                (ImageSize::S1792x1024, hd.clone(), 0.12),
+                (ImageSize::S1024x1792, hd, 0.12),
+            ]),
+        ),
+        (
+            "dall-e-2",
+            None,
+            None,
+            dall_e_2,
+            images(&[
+                (ImageSize::S1024x1024, standard.clone(), 0.02),
+                (ImageSize::S512x512, standard.clone(), 0.018),
+                (ImageSize::S256x256, standard, 0.016),
+            ]),
+        ),
+        (
+            "tts-1",
+            None,
+            None,
+            Default::default(),
+            ModelPricing::Characters { per_million: 15.0 },
+        ),
+        (
+            "tts-1-hd",
+            None,
+            None,
+            Default::default(),
+            ModelPricing::Characters { per_million: 30.0 },
+        ),
+        (
+            "whisper-1",
+            None,
+            None,
+            Default::default(),
+            ModelPricing::Minutes { per_minute: 0.006 },
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ModelCatalog, ModelFeatures, ModelInfo};
+    use crate::types::{
+        ChatCompletionRequestMessageContentPartImageArgs, ChatCompletionRequestUserMessageArgs,
+        CompletionUsage, CreateChatCompletionRequestArgs, CreateEmbeddingRequestArgs,
+        CreateImageRequestArgs, CreateImageVariationRequestArgs, ImageModel, ImageQuality,
+        ImageSize, ImageStyle, ImageUrlArgs,
+    };
+
+    #[test]
+    fn test_model_catalog() {
+        let catalog = ModelCatalog::default();
+
+        assert_eq!(
+            catalog.get("gpt-4-32k-0613").unwrap().context_window,
+            Some(32_768)
+        );
+        assert_eq!(
+            catalog.get("ft:gpt-3.5-turbo-1106:org::8abc").unwrap(),
+            catalog.get("gpt-3.5-turbo-1106").unwrap()
+        );
+        assert!(catalog.get("gpt-40").is_none());
+        assert!(catalog.get("gpt-4-0613-custom").is_none());
+
+        // Newer models don't fall back to the entry of an older model with a common prefix
+        let gpt_4_turbo = catalog.get("gpt-4-turbo").unwrap();
+        assert_ne!(gpt_4_turbo, catalog.get("gpt-4").unwrap());
+        assert_eq!(gpt_4_turbo.context_window, Some(128_000));
+        assert!(gpt_4_turbo.features.json_mode);
+        assert_eq!(catalog.get("gpt-4-turbo-2024-04-09").unwrap(), gpt_4_turbo);
+        assert_eq!(
+            catalog.get("gpt-4o-2024-05-13").unwrap(),
+            catalog.get("gpt-4o").unwrap()
+        );
+
+        let usage = CompletionUsage {
+            prompt_tokens: 1000,
+            completion_tokens: 500,
+            total_tokens: 1500,
+        };
+        assert_eq!(catalog.cost("gpt-4-0613", &usage), Some(0.06));
+        assert_eq!(catalog.cost("dall-e-3", &usage), None);
+
+        let catalog = catalog.with_model("gpt-4", ModelInfo::default());
+        assert_eq!(catalog.cost("gpt-4-0613", &usage), None);
+    }
+
+    #[test]
+    fn test_check_requests() {
+        let catalog = ModelCatalog::default();
+        let image = ChatCompletionRequestUserMessageArgs::default()
+            .content(vec![
+                ChatCompletionRequestMessageContentPartImageArgs::default()
+                    .image_url(
+                        ImageUrlArgs::default()
+                            .url("https://example.com/image.png")
+                            .build()
+                            .unwrap(),
+                    )
+                    .build()
+                    .unwrap()
+                    .into(),
+            ])
+            .build()
+            .unwrap();
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-4-vision-preview")
+            .max_tokens(4096_u16)
+            .messages([image.clone().into()])
+            .build()
+            .unwrap();
+        assert!(catalog.check_chat_request(&request).is_ok());
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-4-0613")
+            .messages([image.into()])
+            .build()
+            .unwrap();
+        assert!(catalog.check_chat_request(&request).is_err());
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .max_tokens(8000_u16)
+            .messages([])
+            .build()
+            .unwrap();
+        assert!(catalog.check_chat_request(&request).is_err());
+
+        let embedding = |model: &str, dimensions: u32| {
+            CreateEmbeddingRequestArgs::default()
+                .model(model)
+                .input("Hello")
+                .dimensions(dimensions)
+                .build()
+                .unwrap()
+        };
+        assert!(catalog
+            .check_embedding_request(&embedding("text-embedding-3-small", 256))
+            .is_ok());
+        assert!(catalog
+            .check_embedding_request(&embedding("text-embedding-3-small", 2048))
+            .is_err());
+        assert!(catalog
+            .check_embedding_request(&embedding("text-embedding-ada-002", 256))
+            .is_err());
+
+        let image = |model: ImageModel, size: ImageSize, quality: ImageQuality| {
+            CreateImageRequestArgs::default()
+                .prompt("A cat")
+                .model(model)
+                .size(size)
+                .quality(quality)
+                .build()
+                .unwrap()
+        };
+        assert!(catalog
+            .check_image_request(&image(
+                ImageModel::DallE3,
+                ImageSize::S1792x1024,
+                ImageQuality::HD
+            ))
+            .is_ok());
+        assert!(catalog
+            .check_image_request(&image(
+                ImageModel::DallE2,
+                ImageSize::S256x256,
+                ImageQuality::Standard
+            ))
+            .is_ok());
+        assert!(catalog
+            .check_image_request(&image(
+                ImageModel::DallE2,
+                ImageSize::S1024x1024,
+                ImageQuality::HD
+            ))
+            .is_err());
+        assert!(catalog
+            .check_image_request(&image(
+                ImageModel::DallE3,
+                ImageSize::S512x512,
+                ImageQuality::Standard
+            ))
+            .is_err());
+
+        let dall_e_3 = || {
+            let mut request = CreateImageRequestArgs::default();
+            request.prompt("A cat").model(ImageModel::DallE3);
+            request
+        };
+        assert!(catalog
+            .check_image_request(&dall_e_3().n(2).build().unwrap())
+            .is_err());
+        assert!(catalog
+            .check_image_request(&dall_e_3().prompt("a".repeat(4001)).build().unwrap())
+            .is_err());
+        assert!(catalog
+            .check_image_request(&dall_e_3().style(ImageStyle::Natural).build().unwrap())
+            .is_ok());
+        assert!(catalog
+            .check_image_request(
+                &dall_e_3()
+                    .model(ImageModel::DallE2)
+                    .style(ImageStyle::Natural)
+                    .build()
+                    .unwrap()
+            )
+            .is_err());
+        let catalog = catalog.with_model(
+            "dall-e-3",
+            ModelInfo {
+                features: ModelFeatures {
+                    max_images: Some(4),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        assert!(catalog
+            .check_image_request(&dall_e_3().n(2).build().unwrap())
+            .is_ok());
+
+        let variation = |model: ImageModel| {
+            CreateImageVariationRequestArgs::default()
+                .image("cat.png")
+                .model(model)
+                .build()
+                .unwrap()
+        };
+        assert!(catalog
+            .check_image_variation_request(&variation(ImageModel::DallE2))
+            .is_ok());
+        assert_eq!(
+            catalog
+                .check_image_variation_request(&variation(ImageModel::DallE3))
+                .unwrap_err()
+                .to_string(),
+            "invalid args: dall-e-3 doesn't support image variations"
+        );
+    }
+
+    #[test]
+    fn test_image_prices() {
+        let catalog = ModelCatalog::default();
+        let price = |model: &str, size: ImageSize, quality: ImageQuality| {
+            catalog
+                .get(model)?
+                .pricing
+                .as_ref()?
+                .image_price(size, &quality)
+        };
+
+        assert_eq!(
+            price("dall-e-3", ImageSize::S1024x1024, ImageQuality::Standard),
+            Some(0.04)
+        );
+        assert_eq!(
+            price("dall-e-3", ImageSize::S1024x1792, ImageQuality::Standard),
+            Some(0.08)
+        );
+        assert_eq!(
+            price("dall-e-3", ImageSize::S1024x1024, ImageQuality::HD),
+            Some(0.08)
+        );
+        assert_eq!(
+            price("dall-e-3", ImageSize::S1792x1024, ImageQuality::HD),
+            Some(0.12)
+        );
+        assert_eq!(
+            price("dall-e-2", ImageSize::S256x256, ImageQuality::Standard),
+            Some(0.016)
+        );
+        assert_eq!(
+            price("dall-e-2", ImageSize::S1024x1024, ImageQuality::HD),
+            None
+        );
+        assert_eq!(
+            price("gpt-4", ImageSize::S1024x1024, ImageQuality::Standard),
+            None
+        );
+    }
+}
@@ -1,8 +1,18 @@
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+
 use crate::{
     config::Config,
-    error::OpenAIError,
+    error::{map_deserialization_error, OpenAIError, ToolLoopLimit},
+    response::Response,
+    stream_recovery::{RecoverableStream, Reissue, StreamRecovery},
+    tools::{FunctionParameters, ToolLoopResponse, ToolRegistry},
     types::{
-        ChatCompletionResponseStream, CreateChatCompletionRequest, CreateChatCompletionResponse,
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
+        ChatCompletionRequestUserMessageArgs, ChatCompletionResponseFormat,
+        ChatCompletionResponseFormatType, ChatCompletionResponseStream,
+        CreateChatCompletionRequest, CreateChatCompletionResponse,
     },
     Client,
 };
@@ -12,11 +22,36 @@ use crate::{
 /// Related guide: [Chat completions](https://platform.openai.com//docs/guides/text-generation)
 pub struct Chat<'c, C: Config> {
     client: &'c Client<C>,
+    stream_recovery: Option<(StreamRecovery, Reissue)>,
+}
+
+impl<'c, C: Config + Send + Sync + 'static> Chat<'c, C> {
+    /// Opt in to recover streams of [Chat::create_stream] which lose their connection before the model finished.
+    pub fn with_stream_recovery(mut self, stream_recovery: StreamRecovery) -> Self {
+        let client = self.client.clone();
+        let reissue: Reissue = Arc::new(move |request| {
+            let client = client.clone();
+            Box::pin(async move {
+                client.validate(&request)?;
+                client.wait_for_rate_limit(&request).await;
+                client.post_stream("/chat/completions", request).await
+            })
+        });
+
+        self.stream_recovery = match stream_recovery {
+            StreamRecovery::Disabled => None,
+            stream_recovery => Some((stream_recovery, reissue)),
+        };
+        self
+    }
 }
 
 impl<'c, C: Config> Chat<'c, C> {
     pub fn new(client: &'c Client<C>) -> Self {
-        Self { client }
+        Self {
+            client,
+            stream_recovery: None,
+        }
     }
 
     /// Creates a model response for the given chat conversation.
@@ -29,7 +64,174 @@ impl<'c, C: Config> Chat<'c, C> {
                 "When stream is true, use Chat::create_stream".into(),
             ));
         }
-        self.client.post("/chat/completions", request).await
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let response: CreateChatCompletionResponse =
+            self.client.post("/chat/completions", request).await?;
+        self.record_usage(&model, user.as_deref(), &response);
+        Ok(response)
+    }
+
+    /// Same as [Chat::create], along with the request id and rate limits from response headers.
+    pub async fn create_with_meta(
+        &self,
+        request: CreateChatCompletionRequest,
+    ) -> Result<Response<CreateChatCompletionResponse>, OpenAIError> {
+        if request.stream.is_some() && request.stream.unwrap() {
+            return Err(OpenAIError::InvalidArgument(
+                "When stream is true, use Chat::create_stream".into(),
+            ));
+        }
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let response: Response<CreateChatCompletionResponse> = self
+            .client
+            .post_with_meta("/chat/completions", request)
+            .await?;
+        self.record_usage(&model, user.as_deref(), &response.data);
+        Ok(response)
+    }
+
+    /// Creates a model response in [JSON mode](https://platform.openai.com/docs/guides/text-generation/json-mode)
+    /// and deserializes its content into `T`.
+    ///
+    /// The system message instructs the model to respond with a JSON object matching the schema of `T`.
+    /// When the content cannot be deserialized, the model is asked to correct it with the error,
+    /// up to `repair_budget` times before failing with [OpenAIError::JSONDeserialize].
+    pub async fn create_typed<T: FunctionParameters>(
+        &self,
+        request: CreateChatCompletionRequest,
+        repair_budget: u32,
+    ) -> Result<T, OpenAIError> {
+        self.create_typed_with_schema(request, T::parameters_schema(), repair_budget)
+            .await
+    }
+
+    /// Same as [Chat::create_typed], for types which don't implement [FunctionParameters],
+    /// with the JSON Schema `schema` of `T`.
+    pub async fn create_typed_with_schema<T: DeserializeOwned>(
+        &self,
+        mut request: CreateChatCompletionRequest,
+        schema: serde_json::Value,
+        repair_budget: u32,
+    ) -> Result<T, OpenAIError> {
+        request.response_format = Some(ChatCompletionResponseFormat {
+            r#type: ChatCompletionResponseFormatType::JsonObject,
+        });
+
+        let instruction = format!(
+            "Respond with a JSON object which validates against this JSON Schema:\n{schema}"
+        );
+        match request.messages.first_mut() {
+            Some(ChatCompletionRequestMessage::System(message)) => {
+                message.content = format!("{}\n\n{instruction}", message.content);
+            }
+            _ => request.messages.insert(
+                0,
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .content(instruction)
+                    .build()?
+                    .into(),
+            ),
+        }
+
+        let mut repairs = 0;
+        loop {
+            let response = self.create(request.clone()).await?;
+            let message = response
+                .choices
+                .into_iter()
+                .next()
+                .map(|choice| choice.message);
+            let content = message
+                .as_ref()
+                .and_then(|message| message.content.clone())
+                .unwrap_or_default();
+
+            let error = match serde_json::from_str::<T>(&content) {
+                Ok(output) => return Ok(output),
+                Err(e) if repairs >= repair_budget => {
+                    return Err(map_deserialization_error(e, content.as_bytes()))
+                }
+                Err(e) => e,
+            };
+
+            repairs += 1;
+            tracing::warn!("Repairing typed response ({repairs}/{repair_budget}): {error}");
+            request.messages.extend(message.map(Into::into));
+            request.messages.push(
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content(format!(
+                        "The JSON object is invalid: {error}. Respond with the corrected JSON object only."
+                    ))
+                    .build()?
+                    .into(),
+            );
+        }
+    }
+
+    /// Creates a model response, running the tools it calls with `tools` and sending their results back,
+    /// until it responds without calling tools. Tool calls of a response run concurrently.
+    ///
+    /// `tools` are set on the request when it has none. Only the first choice is continued when `n` is greater than 1.
+    /// Fails with [OpenAIError::ToolLoopLimit], without running them, when the model still calls tools
+    /// in its `max_iterations`-th response. `max_iterations` must be at least 1.
+    pub async fn create_with_tools(
+        &self,
+        mut request: CreateChatCompletionRequest,
+        tools: &ToolRegistry,
+        max_iterations: u32,
+    ) -> Result<ToolLoopResponse, OpenAIError> {
+        if max_iterations == 0 {
+            return Err(OpenAIError::InvalidArgument(
+                "max_iterations must be at least 1".into(),
+            ));
+        }
+        if request.tools.is_none() {
+            request.tools = Some(tools.tools());
+        }
+
+        let mut iterations = 0;
+        loop {
+            iterations += 1;
+            let response = self.create(request.clone()).await?;
+
+            let message = match response.choices.first() {
+                Some(choice) => choice.message.clone(),
+                None => {
+                    return Ok(ToolLoopResponse {
+                        response,
+                        messages: request.messages,
+                    })
+                }
+            };
+            let tool_calls = message.tool_calls.clone().unwrap_or_default();
+            request.messages.push(message.into());
+
+            if tool_calls.is_empty() {
+                return Ok(ToolLoopResponse {
+                    response,
+                    messages: request.messages,
+                });
+            }
+            if iterations >= max_iterations {
+                return Err(OpenAIError::ToolLoopLimit(ToolLoopLimit {
+                    max_iterations,
+                    tool_calls,
+                    messages: request.messages,
+                }));
+            }
+
+            request.messages.extend(
+                tools
+                    .call_all(&tool_calls)
+                    .await
+                    .into_iter()
+                    .map(Into::into),
+            );
+        }
     }
 
     /// Creates a completion for the chat message
@@ -37,6 +239,9 @@ impl<'c, C: Config> Chat<'c, C> {
     /// partial message deltas will be sent, like in ChatGPT. Tokens will be sent as data-only [server-sent events](https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#Event_stream_format) as they become available, with the stream terminated by a `data: [DONE]` message.
     ///
     /// [ChatCompletionResponseStream] is a parsed SSE stream until a \[DONE\] is received from server.
+    ///
+    /// A connection lost before the model finished is an error item of the stream,
+    /// unless handled as set with [Chat::with_stream_recovery].
     pub async fn create_stream(
         &self,
         mut request: CreateChatCompletionRequest,
@@ -48,7 +253,264 @@ impl<'c, C: Config> Chat<'c, C> {
         }
 
         request.stream = Some(true);
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
+
+        let stream = self
+            .client
+            .post_stream("/chat/completions", request.clone())
+            .await?;
+
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let stream = match &self.stream_recovery {
+            None => stream,
+            Some((stream_recovery, reissue)) => {
+                RecoverableStream::new(request, stream, *stream_recovery, reissue.clone()).boxed()
+            }
+        };
+
+        Ok(self
+            .client
+            .track_stream_usage("chat", model, user, stream, |chunk| chunk.usage.as_ref()))
+    }
+
+    fn record_usage(
+        &self,
+        model: &str,
+        user: Option<&str>,
+        response: &CreateChatCompletionResponse,
+    ) {
+        match &response.usage {
+            Some(usage) => self.client.record_usage("chat", model, user, usage.into()),
+            None => self.client.record_request("chat", model, user),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::{
+        error::OpenAIError,
+        testing::{MockResponse, MockServer},
+        tools::ToolRegistry,
+        types::{
+            ChatCompletionRequestMessage, ChatCompletionRequestUserMessageArgs,
+            CreateChatCompletionRequest, CreateChatCompletionRequestArgs, FunctionObjectArgs,
+        },
+    };
+
+    #[derive(Deserialize)]
+    struct AddArgs {
+        a: i64,
+        b: i64,
+    }
+
+    fn add_tool() -> ToolRegistry {
+        ToolRegistry::new().with_tool(
+            FunctionObjectArgs::default().name("add").build().unwrap(),
+            |args: AddArgs| async move { Ok::<_, String>(args.a + args.b) },
+        )
+    }
+
+    fn add_request() -> CreateChatCompletionRequest {
+        CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .messages([ChatCompletionRequestUserMessageArgs::default()
+                .content("What is 1 + 2?")
+                .build()
+                .unwrap()
+                .into()])
+            .build()
+            .unwrap()
+    }
+
+    fn completion(message: serde_json::Value) -> MockResponse {
+        MockResponse::json(&json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1694268190,
+            "model": "gpt-3.5-turbo",
+            "choices": [{"index": 0, "message": message, "finish_reason": "stop"}]
+        }))
+    }
+
+    fn tool_call_completion(id: &str) -> MockResponse {
+        completion(json!({
+            "role": "assistant",
+            "content": null,
+            "tool_calls": [{
+                "id": id,
+                "type": "function",
+                "function": {"name": "add", "arguments": "{\"a\": 1, \"b\": 2}"}
+            }]
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_create_with_tools() {
+        let server = MockServer::start().await;
+        server
+            .mock("POST", "/chat/completions", tool_call_completion("call_1"))
+            .mock(
+                "POST",
+                "/chat/completions",
+                completion(json!({"role": "assistant", "content": "1 + 2 = 3"})),
+            );
+        let client = server.client();
+
+        let response = client
+            .chat()
+            .create_with_tools(add_request(), &add_tool(), 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.response.choices[0].message.content.as_deref(),
+            Some("1 + 2 = 3")
+        );
+        // User message, tool call, tool result and final answer
+        assert_eq!(response.messages.len(), 4);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let request: serde_json::Value = requests[1].json().unwrap();
+        assert_eq!(request["tools"][0]["function"]["name"], "add");
+        assert_eq!(request["messages"][2]["role"], "tool");
+        assert_eq!(request["messages"][2]["tool_call_id"], "call_1");
+        assert_eq!(request["messages"][2]["content"], "3");
+    }
+
+    #[tokio::test]
+    async fn test_create_with_tools_limit() {
+        let server = MockServer::start().await;
+        server
+            .mock("POST", "/chat/completions", tool_call_completion("call_1"))
+            .mock("POST", "/chat/completions", tool_call_completion("call_2"));
+        let client = server.client();
+
+        let error = client
+            .chat()
+            .create_with_tools(add_request(), &add_tool(), 2)
+            .await
+            .unwrap_err();
+        let limit = match error {
+            OpenAIError::ToolLoopLimit(limit) => limit,
+            e => panic!("unexpected error {e}"),
+        };
+        assert_eq!(limit.max_iterations, 2);
+        // Tool calls of the last response are returned instead of being run
+        assert_eq!(limit.tool_calls.len(), 1);
+        assert_eq!(limit.tool_calls[0].id, "call_2");
+        assert_eq!(limit.messages.len(), 4);
+        assert!(matches!(
+            limit.messages.last(),
+            Some(ChatCompletionRequestMessage::Assistant(_))
+        ));
+        assert_eq!(server.requests().len(), 2);
+
+        let error = client
+            .chat()
+            .create_with_tools(add_request(), &add_tool(), 0)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, OpenAIError::InvalidArgument(_)));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Sum {
+        sum: i64,
+    }
+
+    fn sum_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {"sum": {"type": "integer"}},
+            "required": ["sum"]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_typed() {
+        let server = MockServer::start().await;
+        server.mock(
+            "POST",
+            "/chat/completions",
+            completion(json!({"role": "assistant", "content": "{\"sum\": 3}"})),
+        );
+        let client = server.client();
+
+        let sum: Sum = client
+            .chat()
+            .create_typed_with_schema(add_request(), sum_schema(), 0)
+            .await
+            .unwrap();
+        assert_eq!(sum, Sum { sum: 3 });
+
+        let request: serde_json::Value = server.requests()[0].json().unwrap();
+        assert_eq!(request["response_format"]["type"], "json_object");
+        assert_eq!(request["messages"][0]["role"], "system");
+        assert!(request["messages"][0]["content"]
+            .as_str()
+            .unwrap()
+            .contains(r#""required":["sum"]"#));
+    }
+
+    #[tokio::test]
+    async fn test_create_typed_repair() {
+        let server = MockServer::start().await;
+        server
+            .mock(
+                "POST",
+                "/chat/completions",
+                completion(json!({"role": "assistant", "content": "{\"sum\": \"three\"}"})),
+            )
+            .mock(
+                "POST",
+                "/chat/completions",
+                completion(json!({"role": "assistant", "content": "{\"sum\": 3}"})),
+            );
+        let client = server.client();
+
+        let sum: Sum = client
+            .chat()
+            .create_typed_with_schema(add_request(), sum_schema(), 1)
+            .await
+            .unwrap();
+        assert_eq!(sum, Sum { sum: 3 });
+
+        // Invalid response and the error are sent back for the model to correct
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let request: serde_json::Value = requests[1].json().unwrap();
+        let messages = request["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[2]["content"], "{\"sum\": \"three\"}");
+        assert!(messages[3]["content"]
+            .as_str()
+            .unwrap()
+            .starts_with("The JSON object is invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_create_typed_repair_budget_exhausted() {
+        let server = MockServer::start().await;
+        server.mock(
+            "POST",
+            "/chat/completions",
+            completion(json!({"role": "assistant", "content": "not json"})),
+        );
+        let client = server.client();
 
-        Ok(self.client.post_stream("/chat/completions", request).await)
+        let error = client
+            .chat()
+            .create_typed_with_schema::<Sum>(add_request(), sum_schema(), 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, OpenAIError::JSONDeserialize(_)));
+        // First attempt and two repairs
+        assert_eq!(server.requests().len(), 3);
     }
 }
@@ -1,27 +1,47 @@
 use std::pin::Pin;
 
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc,
+};
+
 use bytes::Bytes;
+use eventsource_stream::{EventStreamError, Eventsource};
 use futures::{stream::StreamExt, Stream};
-use reqwest_eventsource::{Event, EventSource, RequestBuilderExt};
 use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
+    catalog::ModelCatalog,
     config::{Config, OpenAIConfig},
     edit::Edits,
-    error::{map_deserialization_error, OpenAIError, WrappedError},
+    error::{map_deserialization_error, ApiError, OpenAIError, WrappedError},
     file::Files,
     image::Images,
+    middleware::{Middleware, MiddlewareStack},
     moderation::Moderations,
+    rate_limiter::{RateLimited, RateLimiter},
+    response::{Response, ResponseMeta},
+    retry::{AttemptFailure, RetryPolicy},
+    types::CompletionUsage,
+    usage::{UsageTotals, UsageTracker},
+    util::retry_after,
+    validate::Validate,
     Assistants, Audio, Chat, Completions, Embeddings, FineTunes, FineTuning, Models, Threads,
 };
 
 #[derive(Debug, Clone)]
-/// Client is a container for config, backoff and http_client
+/// Client is a container for config, backoff, retry policy, middlewares, rate limiter, usage tracker and http_client
 /// used to make API calls.
 pub struct Client<C: Config> {
     http_client: reqwest::Client,
     config: C,
     backoff: backoff::ExponentialBackoff,
+    middleware: MiddlewareStack,
+    rate_limiter: Option<RateLimiter>,
+    retry_policy: RetryPolicy,
+    usage_tracker: Option<UsageTracker>,
+    /// Catalog of the model rules of request validation, `None` when validation is disabled.
+    validation: Option<Arc<ModelCatalog>>,
 }
 
 impl Client<OpenAIConfig> {
@@ -31,6 +51,11 @@ impl Client<OpenAIConfig> {
             http_client: reqwest::Client::new(),
             config: OpenAIConfig::default(),
             backoff: Default::default(),
+            middleware: Default::default(),
+            rate_limiter: None,
+            retry_policy: Default::default(),
+            usage_tracker: None,
+            validation: None,
         }
     }
 }
@@ -42,6 +67,11 @@ impl<C: Config> Client<C> {
             http_client: reqwest::Client::new(),
             config,
             backoff: Default::default(),
+            middleware: Default::default(),
+            rate_limiter: None,
+            retry_policy: Default::default(),
+            usage_tracker: None,
+            validation: None,
         }
     }
 
@@ -53,12 +83,66 @@ impl<C: Config> Client<C> {
         self
     }
 
-    /// Exponential backoff for retrying [rate limited](https://platform.openai.com/docs/guides/rate-limits) requests.
+    /// Exponential backoff for retrying [rate limited](https://platform.openai.com/docs/guides/rate-limits) requests,
+    /// and other failures retried by the [RetryPolicy].
+    ///
+    /// When the server specifies a delay in `Retry-After` or `x-ratelimit-reset-*` response headers,
+    /// it is used instead of the next exponential interval, still bounded by `max_elapsed_time`.
     pub fn with_backoff(mut self, backoff: backoff::ExponentialBackoff) -> Self {
         self.backoff = backoff;
         self
     }
 
+    /// Decide which failures are retried, see [RetryPolicy::default] for the default rules.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Add a [Middleware] to be invoked around every HTTP request made by this client.
+    ///
+    /// Middlewares are stacked: `before_request` hooks run in the order they were added,
+    /// `after_response` and `on_error` hooks run in reverse order.
+    pub fn with_middleware<M: Middleware + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Throttle chat, completion and embedding requests on the client side with a [RateLimiter],
+    /// calls wait for capacity instead of being sent and rate limited by the server.
+    ///
+    /// All clones of this client share the budget of the rate limiter.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Check requests against their documented constraints, and the rules of their model in the built-in
+    /// [ModelCatalog], before sending them, see [Validate]. Disabled by default.
+    pub fn with_request_validation(mut self, validate_requests: bool) -> Self {
+        self.validation = validate_requests.then(|| Arc::new(ModelCatalog::default()));
+        self
+    }
+
+    /// Enable request validation, see [Client::with_request_validation], with the model rules of `catalog`.
+    pub fn with_validation_catalog(mut self, catalog: ModelCatalog) -> Self {
+        self.validation = Some(Arc::new(catalog));
+        self
+    }
+
+    /// Record the usage and estimated cost of API calls made by this client in a [UsageTracker].
+    ///
+    /// All clones of this client record to the same tracker, until they are given another one.
+    pub fn with_usage_tracker(mut self, usage_tracker: UsageTracker) -> Self {
+        self.usage_tracker = Some(usage_tracker);
+        self
+    }
+
+    /// Tracker recording the usage of this client, set with [Client::with_usage_tracker].
+    pub fn usage_tracker(&self) -> Option<&UsageTracker> {
+        self.usage_tracker.as_ref()
+    }
+
     // API groups
 
     /// To call [Models] group related APIs using this client.
@@ -132,6 +216,78 @@ impl<C: Config> Client<C> {
         &self.config
     }
 
+    /// Wait for capacity in the budget of the [RateLimiter], if any, to send `request`.
+    pub(crate) async fn wait_for_rate_limit<R: RateLimited>(&self, request: &R) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter
+                .acquire(request.model(), request.estimated_tokens())
+                .await;
+        }
+    }
+
+    /// Validate `request` when enabled with [Client::with_request_validation].
+    pub(crate) fn validate<R: Validate>(&self, request: &R) -> Result<(), OpenAIError> {
+        if let Some(catalog) = &self.validation {
+            request.validate()?;
+            request.validate_model(catalog)?;
+        }
+        Ok(())
+    }
+
+    /// Record a request to `model` through the `api` group in the [UsageTracker], if any.
+    pub(crate) fn record_usage(
+        &self,
+        api: &str,
+        model: &str,
+        user: Option<&str>,
+        usage: UsageTotals,
+    ) {
+        if let Some(usage_tracker) = &self.usage_tracker {
+            usage_tracker.record(
+                api,
+                model,
+                user,
+                UsageTotals {
+                    requests: 1,
+                    ..usage
+                },
+            );
+        }
+    }
+
+    /// Record a request to `model` whose usage is unknown in the [UsageTracker], if any.
+    pub(crate) fn record_request(&self, api: &str, model: &str, user: Option<&str>) {
+        if let Some(usage_tracker) = &self.usage_tracker {
+            usage_tracker.record_request(api, model, user);
+        }
+    }
+
+    /// Record a streamed request in the [UsageTracker], if any, along with the usage reported by its chunks.
+    pub(crate) fn track_stream_usage<O>(
+        &self,
+        api: &'static str,
+        model: String,
+        user: Option<String>,
+        stream: Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>,
+        usage: fn(&O) -> Option<&CompletionUsage>,
+    ) -> Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>
+    where
+        O: Send + 'static,
+    {
+        let Some(usage_tracker) = self.usage_tracker.clone() else {
+            return stream;
+        };
+
+        usage_tracker.record_request(api, &model, user.as_deref());
+        stream
+            .inspect(move |chunk| {
+                if let Some(usage) = chunk.as_ref().ok().and_then(usage) {
+                    usage_tracker.record(api, &model, user.as_deref(), usage.into());
+                }
+            })
+            .boxed()
+    }
+
     /// Make a GET request to {path} and deserialize the response body
     pub(crate) async fn get<O>(&self, path: &str) -> Result<O, OpenAIError>
     where
@@ -222,6 +378,29 @@ impl<C: Config> Client<C> {
         self.execute(request_maker).await
     }
 
+    /// Make a POST request to {path} and deserialize the response body along with its [ResponseMeta]
+    pub(crate) async fn post_with_meta<I, O>(
+        &self,
+        path: &str,
+        request: I,
+    ) -> Result<Response<O>, OpenAIError>
+    where
+        I: Serialize,
+        O: DeserializeOwned,
+    {
+        let request_maker = || async {
+            Ok(self
+                .http_client
+                .post(self.config.url(path))
+                .query(&self.config.query())
+                .headers(self.config.headers())
+                .json(&request)
+                .build()?)
+        };
+
+        self.execute_with_meta(request_maker).await
+    }
+
     /// POST a form at {path} and deserialize the response body
     pub(crate) async fn post_form<O, F>(&self, path: &str, form: F) -> Result<O, OpenAIError>
     where
@@ -252,52 +431,149 @@ impl<C: Config> Client<C> {
         M: Fn() -> Fut,
         Fut: core::future::Future<Output = Result<reqwest::Request, OpenAIError>>,
     {
-        let client = self.http_client.clone();
-
-        backoff::future::retry(self.backoff.clone(), || async {
-            let request = request_maker().await.map_err(backoff::Error::Permanent)?;
-            let response = client
-                .execute(request)
-                .await
-                .map_err(OpenAIError::Reqwest)
-                .map_err(backoff::Error::Permanent)?;
-
-            let status = response.status();
-            let bytes = response
-                .bytes()
-                .await
-                .map_err(OpenAIError::Reqwest)
-                .map_err(backoff::Error::Permanent)?;
-
-            // Deserialize response body from either error object or actual response object
-            if !status.is_success() {
-                let wrapped_error: WrappedError = serde_json::from_slice(bytes.as_ref())
-                    .map_err(|e| map_deserialization_error(e, bytes.as_ref()))
-                    .map_err(backoff::Error::Permanent)?;
-
-                if status.as_u16() == 429
-                    // API returns 429 also when:
-                    // "You exceeded your current quota, please check your plan and billing details."
-                    && wrapped_error.error.r#type != Some("insufficient_quota".to_string())
+        let (bytes, _meta) = self.execute_raw_with_meta(request_maker).await?;
+        Ok(bytes)
+    }
+
+    /// Same as [Client::execute_raw] but also returns [ResponseMeta] of the successful response.
+    async fn execute_raw_with_meta<M, Fut>(
+        &self,
+        request_maker: M,
+    ) -> Result<(Bytes, ResponseMeta), OpenAIError>
+    where
+        M: Fn() -> Fut,
+        Fut: core::future::Future<Output = Result<reqwest::Request, OpenAIError>>,
+    {
+        let started = std::time::Instant::now();
+        let attempts = AtomicU32::new(0);
+        let backoff = self.retry_policy.backoff(&self.backoff);
+
+        backoff::future::retry(backoff, || async {
+            let attempt = attempts.fetch_add(1, Ordering::Relaxed) + 1;
+
+            let result = match self.execute_once(&request_maker).await {
+                Err(backoff::Error::Transient { err, .. })
+                    if !self.retry_policy.allows_attempt(attempt) =>
                 {
-                    // Rate limited retry...
-                    tracing::warn!("Rate limited: {}", wrapped_error.error.message);
-                    return Err(backoff::Error::Transient {
-                        err: OpenAIError::ApiError(wrapped_error.error),
-                        retry_after: None,
-                    });
-                } else {
-                    return Err(backoff::Error::Permanent(OpenAIError::ApiError(
-                        wrapped_error.error,
-                    )));
+                    Err(backoff::Error::Permanent(err))
                 }
+                // Delay requested by the server replaces the next exponential backoff,
+                // give up if waiting for it would exceed the maximum elapsed time.
+                Err(backoff::Error::Transient {
+                    err,
+                    retry_after: Some(delay),
+                }) => {
+                    let delay = self.retry_policy.jittered(delay);
+                    if self
+                        .backoff
+                        .max_elapsed_time
+                        .is_some_and(|max| started.elapsed() + delay > max)
+                    {
+                        Err(backoff::Error::Permanent(err))
+                    } else {
+                        Err(backoff::Error::Transient {
+                            err,
+                            retry_after: Some(delay),
+                        })
+                    }
+                }
+                result => result,
+            };
+
+            if let Err(backoff::Error::Permanent(e) | backoff::Error::Transient { err: e, .. }) =
+                &result
+            {
+                self.middleware.on_error(e).await;
             }
 
-            Ok(bytes)
+            result
         })
         .await
     }
 
+    /// Single attempt of [Client::execute_raw], errors are classified as permanent or transient for backoff.
+    async fn execute_once<M, Fut>(
+        &self,
+        request_maker: &M,
+    ) -> Result<(Bytes, ResponseMeta), backoff::Error<OpenAIError>>
+    where
+        M: Fn() -> Fut,
+        Fut: core::future::Future<Output = Result<reqwest::Request, OpenAIError>>,
+    {
+        let response = self
+            .send(request_maker)
+            .await
+            .map_err(|e| self.classify_transport_error(e))?;
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| self.classify_transport_error(OpenAIError::Reqwest(e)))?;
+
+        // Deserialize response body from either error object or actual response object
+        if !status.is_success() {
+            let wrapped_error = serde_json::from_slice::<WrappedError>(bytes.as_ref());
+
+            let retryable = self.retry_policy.is_retryable(&AttemptFailure::Response {
+                status,
+                error: wrapped_error.as_ref().ok().map(|wrapped| &wrapped.error),
+            });
+
+            let err = api_error(wrapped_error, status, headers.clone(), bytes.as_ref());
+
+            if retryable {
+                let retry_after = retry_after(&headers);
+                tracing::warn!("Retrying {status} after {retry_after:?}: {err}");
+                return Err(backoff::Error::Transient { err, retry_after });
+            } else {
+                return Err(backoff::Error::Permanent(err));
+            }
+        }
+
+        Ok((bytes, ResponseMeta::new(status, headers)))
+    }
+
+    /// Errors from sending a request or reading its response are retried when the [RetryPolicy] allows it.
+    fn classify_transport_error(&self, error: OpenAIError) -> backoff::Error<OpenAIError> {
+        match &error {
+            OpenAIError::Reqwest(e)
+                if self
+                    .retry_policy
+                    .is_retryable(&AttemptFailure::Transport(e)) =>
+            {
+                tracing::warn!("Retrying: {error}");
+                backoff::Error::Transient {
+                    err: error,
+                    retry_after: None,
+                }
+            }
+            _ => backoff::Error::Permanent(error),
+        }
+    }
+
+    /// Make the request, pass it through middlewares and send it.
+    /// Response body is left unread for the caller.
+    async fn send<M, Fut>(&self, request_maker: &M) -> Result<reqwest::Response, OpenAIError>
+    where
+        M: Fn() -> Fut,
+        Fut: core::future::Future<Output = Result<reqwest::Request, OpenAIError>>,
+    {
+        let mut request = request_maker().await?;
+        self.middleware.before_request(&mut request).await?;
+
+        let response = self
+            .http_client
+            .execute(request)
+            .await
+            .map_err(OpenAIError::Reqwest)?;
+
+        self.middleware.after_response(&response).await?;
+
+        Ok(response)
+    }
+
     /// Execute a HTTP request and retry on rate limit
     ///
     /// request_maker serves one purpose: to be able to create request again
@@ -317,26 +593,45 @@ impl<C: Config> Client<C> {
         Ok(response)
     }
 
+    /// Same as [Client::execute] but also returns [ResponseMeta] of the successful response.
+    async fn execute_with_meta<O, M, Fut>(
+        &self,
+        request_maker: M,
+    ) -> Result<Response<O>, OpenAIError>
+    where
+        O: DeserializeOwned,
+        M: Fn() -> Fut,
+        Fut: core::future::Future<Output = Result<reqwest::Request, OpenAIError>>,
+    {
+        let (bytes, meta) = self.execute_raw_with_meta(request_maker).await?;
+
+        let data: O = serde_json::from_slice(bytes.as_ref())
+            .map_err(|e| map_deserialization_error(e, bytes.as_ref()))?;
+
+        Ok(Response { data, meta })
+    }
+
     /// Make HTTP POST request to receive SSE
     pub(crate) async fn post_stream<I, O>(
         &self,
         path: &str,
         request: I,
-    ) -> Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>, OpenAIError>
     where
         I: Serialize,
         O: DeserializeOwned + std::marker::Send + 'static,
     {
-        let event_source = self
-            .http_client
-            .post(self.config.url(path))
-            .query(&self.config.query())
-            .headers(self.config.headers())
-            .json(&request)
-            .eventsource()
-            .unwrap();
+        let request_maker = || async {
+            Ok(self
+                .http_client
+                .post(self.config.url(path))
+                .query(&self.config.query())
+                .headers(self.config.headers())
+                .json(&request)
+                .build()?)
+        };
 
-        stream(event_source).await
+        self.execute_stream(request_maker).await
     }
 
     /// Make HTTP GET request to receive SSE
@@ -344,66 +639,365 @@ impl<C: Config> Client<C> {
         &self,
         path: &str,
         query: &Q,
-    ) -> Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>, OpenAIError>
     where
         Q: Serialize + ?Sized,
         O: DeserializeOwned + std::marker::Send + 'static,
     {
-        let event_source = self
-            .http_client
-            .get(self.config.url(path))
-            .query(query)
-            .query(&self.config.query())
-            .headers(self.config.headers())
-            .eventsource()
-            .unwrap();
+        let request_maker = || async {
+            Ok(self
+                .http_client
+                .get(self.config.url(path))
+                .query(query)
+                .query(&self.config.query())
+                .headers(self.config.headers())
+                .build()?)
+        };
+
+        self.execute_stream(request_maker).await
+    }
+
+    /// Send a HTTP request which responds with SSE.
+    /// Failures before the stream is opened, including a non success response, are returned as an error.
+    async fn execute_stream<O, M, Fut>(
+        &self,
+        request_maker: M,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>, OpenAIError>
+    where
+        O: DeserializeOwned + std::marker::Send + 'static,
+        M: Fn() -> Fut,
+        Fut: core::future::Future<Output = Result<reqwest::Request, OpenAIError>>,
+    {
+        let response = match self.open_stream(&request_maker).await {
+            Ok(response) => response,
+            Err(e) => {
+                self.middleware.on_error(&e).await;
+                return Err(e);
+            }
+        };
 
-        stream(event_source).await
+        Ok(stream(response, self.middleware.clone()))
     }
+
+    /// Send a HTTP request which responds with SSE, and decode the error object of a non success response.
+    /// Success responses which are not `text/event-stream` are rejected.
+    async fn open_stream<M, Fut>(&self, request_maker: M) -> Result<reqwest::Response, OpenAIError>
+    where
+        M: Fn() -> Fut,
+        Fut: core::future::Future<Output = Result<reqwest::Request, OpenAIError>>,
+    {
+        let response = self.send(&request_maker).await?;
+
+        if response.status().is_success() {
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or_default();
+            if !content_type.starts_with("text/event-stream") {
+                return Err(OpenAIError::StreamError(format!(
+                    "expected content type text/event-stream, got {content_type:?}"
+                )));
+            }
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = response.bytes().await?;
+        let wrapped_error = serde_json::from_slice::<WrappedError>(bytes.as_ref());
+
+        Err(api_error(wrapped_error, status, headers, bytes.as_ref()))
+    }
+}
+
+/// Error of a non success response. A body which is not an error object, such as the HTML page of a proxy,
+/// becomes the message of the error so that the status and headers are kept.
+fn api_error(
+    wrapped_error: Result<WrappedError, serde_json::Error>,
+    status: reqwest::StatusCode,
+    headers: reqwest::header::HeaderMap,
+    bytes: &[u8],
+) -> OpenAIError {
+    let error = match wrapped_error {
+        Ok(wrapped_error) => wrapped_error.error,
+        Err(e) => {
+            let body = String::from_utf8_lossy(bytes);
+            tracing::error!("failed deserialization of error response: {e}: {body}");
+            let message = match body.trim() {
+                "" => status.canonical_reason().unwrap_or_default().to_string(),
+                body => body.to_string(),
+            };
+            ApiError {
+                message,
+                ..Default::default()
+            }
+        }
+    };
+    OpenAIError::ApiError(error.with_response(status, headers))
 }
 
 /// Request which responds with SSE.
 /// [server-sent events](https://developer.mozilla.org/en-US/docs/Web/API/Server-sent_events/Using_server-sent_events#event_stream_format)
-pub(crate) async fn stream<O>(
-    mut event_source: EventSource,
+///
+/// Events are decoded as the stream is polled, so a slow consumer slows down reading from the connection,
+/// and dropping the stream closes the connection.
+pub(crate) fn stream<O>(
+    response: reqwest::Response,
+    middleware: MiddlewareStack,
 ) -> Pin<Box<dyn Stream<Item = Result<O, OpenAIError>> + Send>>
 where
     O: DeserializeOwned + std::marker::Send + 'static,
 {
-    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let event_stream = Box::pin(response.bytes_stream().eventsource());
 
-    tokio::spawn(async move {
-        while let Some(ev) = event_source.next().await {
-            match ev {
+    Box::pin(futures::stream::unfold(
+        (event_stream, middleware),
+        |(mut event_stream, middleware)| async move {
+            let response = match event_stream.next().await? {
                 Err(e) => {
-                    if let Err(_e) = tx.send(Err(OpenAIError::StreamError(e.to_string()))) {
-                        // rx dropped
-                        break;
-                    }
+                    let e = match e {
+                        // Connection lost or body could not be read
+                        EventStreamError::Transport(e) => OpenAIError::Reqwest(e),
+                        e => OpenAIError::StreamError(e.to_string()),
+                    };
+                    middleware.on_error(&e).await;
+                    Err(e)
                 }
-                Ok(event) => match event {
-                    Event::Message(message) => {
-                        if message.data == "[DONE]" {
-                            break;
-                        }
-
-                        let response = match serde_json::from_str::<O>(&message.data) {
-                            Err(e) => Err(map_deserialization_error(e, message.data.as_bytes())),
-                            Ok(output) => Ok(output),
-                        };
-
-                        if let Err(_e) = tx.send(response) {
-                            // rx dropped
-                            break;
-                        }
+                Ok(message) => {
+                    if message.data == "[DONE]" {
+                        return None;
                     }
-                    Event::Open => continue,
-                },
+
+                    match serde_json::from_str::<O>(&message.data) {
+                        Ok(output) => Ok(output),
+                        // Server failed after the stream started
+                        Err(e) => match serde_json::from_str::<WrappedError>(&message.data) {
+                            Ok(wrapped_error) => {
+                                let e = OpenAIError::ApiError(wrapped_error.error);
+                                middleware.on_error(&e).await;
+                                Err(e)
+                            }
+                            Err(_) => Err(map_deserialization_error(e, message.data.as_bytes())),
+                        },
+                    }
+                }
+            };
+
+            Some((response, (event_stream, middleware)))
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        error::{ApiErrorKind, OpenAIError},
+        testing::{MockResponse, MockServer},
+        types::{ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs},
+    };
+
+    fn chat_request() -> crate::types::CreateChatCompletionRequest {
+        CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .messages([ChatCompletionRequestUserMessageArgs::default()
+                .content("Hello!")
+                .build()
+                .unwrap()
+                .into()])
+            .build()
+            .unwrap()
+    }
+
+    fn event(content: &str) -> String {
+        let chunk = json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion.chunk",
+            "created": 1694268190,
+            "model": "gpt-3.5-turbo",
+            "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+        });
+        format!("data: {chunk}\n\n")
+    }
+
+    fn sse<I: IntoIterator<Item = String>>(chunks: I, interval: Duration) -> MockResponse {
+        MockResponse::chunked(chunks, interval).with_header("content-type", "text/event-stream")
+    }
+
+    /// Content of the stream items, and the errors as strings.
+    async fn contents(
+        stream: crate::types::ChatCompletionResponseStream,
+    ) -> Vec<Result<String, String>> {
+        stream
+            .map(|item| {
+                item.map(|chunk| chunk.choices[0].delta.content.clone().unwrap_or_default())
+                    .map_err(|e| e.to_string())
+            })
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_stream_events() {
+        let server = MockServer::start().await;
+        let (first, second) = (event("Hello"), event(" world"));
+        let (head, tail) = first.split_at(first.len() / 2);
+        server.mock(
+            "POST",
+            "/chat/completions",
+            sse(
+                [
+                    // Event split across reads
+                    head.to_string(),
+                    tail.to_string() + &second[..3],
+                    second[3..].to_string(),
+                    // Events after [DONE] are ignored
+                    "data: [DONE]\n\n".to_string() + &event("!"),
+                ],
+                Duration::from_millis(10),
+            ),
+        );
+        let client = server.client();
+
+        let stream = client.chat().create_stream(chat_request()).await.unwrap();
+        assert_eq!(
+            contents(stream).await,
+            [Ok("Hello".to_string()), Ok(" world".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_event() {
+        let server = MockServer::start().await;
+        let error = json!({
+            "error": {"message": "The server had an error", "type": "server_error", "param": null, "code": null}
+        });
+        server.mock(
+            "POST",
+            "/chat/completions",
+            sse(
+                [event("Hello"), format!("data: {error}\n\n")],
+                Duration::ZERO,
+            ),
+        );
+        let client = server.client();
+
+        let mut stream = client.chat().create_stream(chat_request()).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        match stream.next().await.unwrap() {
+            Err(OpenAIError::ApiError(error)) => {
+                assert_eq!(error.message, "The server had an error");
+                assert_eq!(error.error_type(), Some(ApiErrorKind::ServerError));
             }
+            item => panic!("unexpected item {item:?}"),
         }
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_dropped_early() {
+        let server = MockServer::start().await;
+        server
+            .mock(
+                "POST",
+                "/chat/completions",
+                sse([event("Hello"), event(" world")], Duration::from_secs(600)),
+            )
+            .mock(
+                "POST",
+                "/chat/completions",
+                sse([event("Hi"), "data: [DONE]\n\n".into()], Duration::ZERO),
+            );
+        let client = server.client();
+
+        // Dropping the stream doesn't wait for the rest of the response
+        let result = tokio::time::timeout(Duration::from_secs(5), async {
+            let mut stream = client.chat().create_stream(chat_request()).await.unwrap();
+            let first = stream.next().await.unwrap().unwrap();
+            drop(stream);
+            first
+        })
+        .await
+        .unwrap();
+        assert_eq!(result.choices[0].delta.content.as_deref(), Some("Hello"));
+
+        let stream = client.chat().create_stream(chat_request()).await.unwrap();
+        assert_eq!(contents(stream).await, [Ok("Hi".to_string())]);
+    }
 
-        event_source.close();
-    });
+    #[tokio::test]
+    async fn test_stream_rejects_non_sse_response() {
+        let server = MockServer::start().await;
+        server.mock(
+            "POST",
+            "/chat/completions",
+            MockResponse::json(&json!({"object": "chat.completion"})),
+        );
+        let client = server.client();
+
+        let error = client
+            .chat()
+            .create_stream(chat_request())
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            matches!(&error, OpenAIError::StreamError(message) if message.contains("application/json")),
+            "{error}"
+        );
+    }
 
-    Box::pin(tokio_stream::wrappers::UnboundedReceiverStream::new(rx))
+    #[tokio::test]
+    async fn test_stream_error_responses() {
+        let server = MockServer::start().await;
+        server
+            .mock(
+                "POST",
+                "/chat/completions",
+                MockResponse::error(
+                    401,
+                    "invalid_request_error",
+                    Some("invalid_api_key"),
+                    "Incorrect API key provided",
+                ),
+            )
+            .mock(
+                "POST",
+                "/chat/completions",
+                MockResponse::bytes("<html><body>502 Bad Gateway</body></html>")
+                    .with_status(500)
+                    .with_header("content-type", "text/html"),
+            );
+        let client = server.client();
+
+        let error = match client.chat().create_stream(chat_request()).await {
+            Err(OpenAIError::ApiError(error)) => error,
+            result => panic!("unexpected result {:?}", result.err()),
+        };
+        assert_eq!(error.status(), Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(error.message, "Incorrect API key provided");
+        assert_eq!(error.error_type(), Some(ApiErrorKind::InvalidRequestError));
+
+        // Body which is not an error object keeps the status
+        let error = match client.chat().create_stream(chat_request()).await {
+            Err(OpenAIError::ApiError(error)) => error,
+            result => panic!("unexpected result {:?}", result.err()),
+        };
+        assert_eq!(error.status(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(error.message.contains("502 Bad Gateway"));
+        assert_eq!(error.r#type, None);
+
+        // Same for requests which don't stream
+        let error = match client.chat().create(chat_request()).await {
+            Err(OpenAIError::ApiError(error)) => error,
+            result => panic!("unexpected result {:?}", result.err()),
+        };
+        assert_eq!(error.status(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+    }
 }
@@ -2,6 +2,7 @@ use crate::{
     client::Client,
     config::Config,
     error::OpenAIError,
+    response::Response,
     types::{CompletionResponseStream, CreateCompletionRequest, CreateCompletionResponse},
 };
 
@@ -30,7 +31,31 @@ impl<'c, C: Config> Completions<'c, C> {
                 "When stream is true, use Completion::create_stream".into(),
             ));
         }
-        self.client.post("/completions", request).await
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let response: CreateCompletionResponse = self.client.post("/completions", request).await?;
+        self.record_usage(&model, user.as_deref(), &response);
+        Ok(response)
+    }
+
+    /// Same as [Completions::create], along with the request id and rate limits from response headers.
+    pub async fn create_with_meta(
+        &self,
+        request: CreateCompletionRequest,
+    ) -> Result<Response<CreateCompletionResponse>, OpenAIError> {
+        if request.stream.is_some() && request.stream.unwrap() {
+            return Err(OpenAIError::InvalidArgument(
+                "When stream is true, use Completion::create_stream".into(),
+            ));
+        }
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let response: Response<CreateCompletionResponse> =
+            self.client.post_with_meta("/completions", request).await?;
+        self.record_usage(&model, user.as_deref(), &response.data);
+        Ok(response)
     }
 
     /// Creates a completion request for the provided prompt and parameters
@@ -51,7 +76,24 @@ impl<'c, C: Config> Completions<'c, C> {
         }
 
         request.stream = Some(true);
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
 
-        Ok(self.client.post_stream("/completions", request).await)
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let stream = self.client.post_stream("/completions", request).await?;
+        Ok(self
+            .client
+            .track_stream_usage("completions", model, user, stream, |chunk| {
+                chunk.usage.as_ref()
+            }))
+    }
+
+    fn record_usage(&self, model: &str, user: Option<&str>, response: &CreateCompletionResponse) {
+        match &response.usage {
+            Some(usage) => self
+                .client
+                .record_usage("completions", model, user, usage.into()),
+            None => self.client.record_request("completions", model, user),
+        }
     }
 }
@@ -0,0 +1,466 @@
+//! Trim conversations to the context window of a model, available with the `tokenizer` feature.
+use std::{fmt, future::Future, ops::Range, pin::Pin, sync::Arc};
+
+use crate::{
+    catalog::ModelCatalog,
+    error::OpenAIError,
+    tokenizer::Tokenizer,
+    types::{
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage, ChatCompletionTool,
+        CreateChatCompletionRequest, Role,
+    },
+};
+
+type SummarizeFn = dyn Fn(
+        Vec<ChatCompletionRequestMessage>,
+    ) -> Pin<Box<dyn Future<Output = Result<String, OpenAIError>> + Send>>
+    + Send
+    + Sync;
+
+/// Caller supplied summarization of dropped messages, usually through another chat completion.
+#[derive(Clone)]
+pub struct Summarizer(Arc<SummarizeFn>);
+
+impl fmt::Debug for Summarizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Summarizer").finish_non_exhaustive()
+    }
+}
+
+/// Which messages [ContextWindow] drops when a conversation doesn't fit.
+///
+/// Messages are dropped in turns, an assistant message calling tools is always kept or dropped
+/// together with the tool (or function) messages answering it, so the request stays valid.
+/// System messages and the last turn are never dropped.
+#[derive(Debug, Clone, Default)]
+pub enum TruncationStrategy {
+    /// Drop the oldest turns first.
+    #[default]
+    DropOldest,
+    /// Keep the `first` and the `last` turns, drop the oldest of the turns in between first.
+    KeepFirstLast { first: usize, last: usize },
+    /// Drop the oldest turns first, and replace them with a system message holding their summary.
+    ///
+    /// The dropped turns are summarized once, in a single call. When the summary doesn't fit,
+    /// the next oldest turns are dropped too, without being summarized.
+    Summarize(Summarizer),
+}
+
+impl TruncationStrategy {
+    /// Summarize dropped messages with `summarize`, the returned text becomes the content of a system message
+    /// at the place of the dropped messages.
+    pub fn summarize<F, Fut>(summarize: F) -> Self
+    where
+        F: Fn(Vec<ChatCompletionRequestMessage>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, OpenAIError>> + Send + 'static,
+    {
+        Self::Summarize(Summarizer(Arc::new(move |messages| {
+            Box::pin(summarize(messages))
+        })))
+    }
+}
+
+/// Fits conversations in the context window of a model, using [Tokenizer] counts.
+///
+/// ```
+/// use async_openai::{
+///     context::{ContextWindow, TruncationStrategy},
+///     types::{ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs},
+/// };
+///
+/// # tokio_test::block_on(async {
+/// let window = ContextWindow::for_model("gpt-3.5-turbo", 4096)?
+///     .with_reserved_tokens(1024)
+///     .with_strategy(TruncationStrategy::KeepFirstLast { first: 1, last: 8 });
+///
+/// let messages = vec![
+///     ChatCompletionRequestSystemMessageArgs::default()
+///         .content("You are a helpful assistant.")
+///         .build()?
+///         .into(),
+///     ChatCompletionRequestUserMessageArgs::default()
+///         .content("Hello!")
+///         .build()?
+///         .into(),
+/// ];
+/// let messages = window.fit(messages).await?;
+/// assert_eq!(messages.len(), 2);
+/// # Ok::<(), async_openai::error::OpenAIError>(())
+/// # });
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContextWindow {
+    tokenizer: Tokenizer,
+    context_length: usize,
+    reserved_tokens: usize,
+    tools: Option<Vec<ChatCompletionTool>>,
+    strategy: TruncationStrategy,
+}
+
+impl ContextWindow {
+    /// Context window of `context_length` tokens, shared by the prompt and the completion.
+    pub fn new(tokenizer: Tokenizer, context_length: usize) -> Self {
+        Self {
+            tokenizer,
+            context_length,
+            reserved_tokens: 0,
+            tools: None,
+            strategy: Default::default(),
+        }
+    }
+
+    /// Context window of `context_length` tokens, counted with the encoding of `model`.
+    pub fn for_model(model: &str, context_length: usize) -> Result<Self, OpenAIError> {
+        Ok(Self::new(Tokenizer::for_model(model)?, context_length))
+    }
+
+    /// Context window of `model` in `catalog`, fails with [OpenAIError::InvalidArgument] when it is unknown.
+    pub fn from_catalog(catalog: &ModelCatalog, model: &str) -> Result<Self, OpenAIError> {
+        let context_length = catalog
+            .get(model)
+            .and_then(|info| info.context_window)
+            .ok_or_else(|| {
+                OpenAIError::InvalidArgument(format!("no known context window for {model}"))
+            })?;
+        Self::for_model(model, context_length as usize)
+    }
+
+    /// Tokens left free for the completion, usually the `max_tokens` of the request.
+    pub fn with_reserved_tokens(mut self, reserved_tokens: usize) -> Self {
+        self.reserved_tokens = reserved_tokens;
+        self
+    }
+
+    /// Tools sent along with the messages, their definitions count against the context window.
+    pub fn with_tools(mut self, tools: Vec<ChatCompletionTool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: TruncationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Prompt tokens of `messages`, including the tool definitions.
+    pub fn count(&self, messages: &[ChatCompletionRequestMessage]) -> usize {
+        self.tokenizer
+            .count_messages(messages, self.tools.as_deref())
+    }
+
+    fn budget(&self) -> usize {
+        self.context_length.saturating_sub(self.reserved_tokens)
+    }
+
+    /// Trim `messages` to fit, they are returned as is when they already fit.
+    ///
+    /// Fails with [OpenAIError::InvalidArgument] when the messages which are never dropped don't fit on their own.
+    pub async fn fit(
+        &self,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) -> Result<Vec<ChatCompletionRequestMessage>, OpenAIError> {
+        let budget = self.budget();
+        let mut total = self.count(&messages);
+        if total <= budget {
+            return Ok(messages);
+        }
+
+        let turns = turns(&messages);
+        let (first, last) = match &self.strategy {
+            TruncationStrategy::KeepFirstLast { first, last } => (*first, *last),
+            _ => (0, 1),
+        };
+
+        // Turns which may be dropped, oldest first
+        let conversation: Vec<usize> = (0..turns.len())
+            .filter(|&turn| !is_system(&messages[turns[turn].start]))
+            .collect();
+        let droppable: Vec<usize> = conversation
+            .iter()
+            .copied()
+            .skip(first)
+            .take(conversation.len().saturating_sub(first + last.max(1)))
+            .collect();
+
+        let summarizer = match &self.strategy {
+            TruncationStrategy::Summarize(summarizer) => Some(summarizer),
+            _ => None,
+        };
+
+        let too_long = |messages: &[ChatCompletionRequestMessage]| {
+            OpenAIError::InvalidArgument(format!(
+                "messages need {} tokens, which doesn't fit in a context window of {} tokens with {} tokens reserved",
+                self.count(messages),
+                self.context_length,
+                self.reserved_tokens
+            ))
+        };
+
+        // Drop turns until the rest fits
+        let mut dropped = vec![false; turns.len()];
+        let mut droppable = droppable.into_iter();
+        while total > budget {
+            let Some(turn) = droppable.next() else {
+                return Err(too_long(&keep(&messages, &turns, &dropped, None)));
+            };
+            dropped[turn] = true;
+            total -= turns[turn]
+                .clone()
+                .map(|index| self.tokenizer.count_message(&messages[index]))
+                .sum::<usize>();
+        }
+
+        let Some(Summarizer(summarize)) = summarizer else {
+            return Ok(keep(&messages, &turns, &dropped, None));
+        };
+
+        // Summarize all the dropped turns at once. The summary takes room too,
+        // drop more turns without summarizing them while it doesn't fit
+        let kept: Vec<bool> = dropped.iter().map(|dropped| !dropped).collect();
+        let summary = summarize(keep(&messages, &turns, &kept, None)).await?;
+        loop {
+            let fitted = keep(&messages, &turns, &dropped, Some(summary.clone()));
+            if self.count(&fitted) <= budget {
+                return Ok(fitted);
+            }
+            let Some(turn) = droppable.next() else {
+                return Err(too_long(&fitted));
+            };
+            dropped[turn] = true;
+        }
+    }
+
+    /// Trim the messages of `request` to fit, counting its tools and reserving its `max_tokens` for the completion.
+    pub async fn fit_request(
+        &self,
+        mut request: CreateChatCompletionRequest,
+    ) -> Result<CreateChatCompletionRequest, OpenAIError> {
+        let mut window = self.clone();
+        if let Some(tools) = &request.tools {
+            window.tools = Some(tools.clone());
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            window.reserved_tokens = max_tokens as usize;
+        }
+
+        let messages = std::mem::take(&mut request.messages);
+        request.messages = window.fit(messages).await?;
+        Ok(request)
+    }
+}
+
+fn is_system(message: &ChatCompletionRequestMessage) -> bool {
+    matches!(message, ChatCompletionRequestMessage::System(_))
+}
+
+/// Ranges of messages which are kept or dropped together:
+/// an assistant message calling tools or a function, followed by the messages with their results.
+#[allow(deprecated)]
+fn turns(messages: &[ChatCompletionRequestMessage]) -> Vec<Range<usize>> {
+    let mut turns = vec![];
+    let mut start = 0;
+
+    while start < messages.len() {
+        let mut end = start + 1;
+        if let ChatCompletionRequestMessage::Assistant(message) = &messages[start] {
+            let calls_tools = message
+                .tool_calls
+                .as_ref()
+                .is_some_and(|calls| !calls.is_empty())
+                || message.function_call.is_some();
+            if calls_tools {
+                while matches!(
+                    messages.get(end),
+                    Some(ChatCompletionRequestMessage::Tool(_))
+                        | Some(ChatCompletionRequestMessage::Function(_))
+                ) {
+                    end += 1;
+                }
+            }
+        }
+        turns.push(start..end);
+        start = end;
+    }
+
+    turns
+}
+
+/// Messages of the turns which are not `dropped`, with the `summary` in place of the first dropped turn.
+fn keep(
+    messages: &[ChatCompletionRequestMessage],
+    turns: &[Range<usize>],
+    dropped: &[bool],
+    mut summary: Option<String>,
+) -> Vec<ChatCompletionRequestMessage> {
+    let mut kept = vec![];
+    for (turn, range) in turns.iter().enumerate() {
+        if !dropped[turn] {
+            kept.extend_from_slice(&messages[range.clone()]);
+        } else if let Some(content) = summary.take() {
+            kept.push(ChatCompletionRequestMessage::System(
+                ChatCompletionRequestSystemMessage {
+                    content,
+                    role: Role::System,
+                    name: None,
+                },
+            ));
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::{ContextWindow, TruncationStrategy};
+    use crate::types::{
+        ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessageArgs,
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessageArgs,
+        ChatCompletionRequestToolMessageArgs, ChatCompletionRequestUserMessageArgs,
+        ChatCompletionToolType, FunctionCall,
+    };
+
+    fn system(content: &str) -> ChatCompletionRequestMessage {
+        ChatCompletionRequestSystemMessageArgs::default()
+            .content(content)
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    fn user(content: &str) -> ChatCompletionRequestMessage {
+        ChatCompletionRequestUserMessageArgs::default()
+            .content(content)
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    fn assistant(content: &str) -> ChatCompletionRequestMessage {
+        ChatCompletionRequestAssistantMessageArgs::default()
+            .content(content)
+            .build()
+            .unwrap()
+            .into()
+    }
+
+    fn conversation() -> Vec<ChatCompletionRequestMessage> {
+        let mut messages = vec![system("You are a helpful assistant.")];
+        for turn in 0..10 {
+            messages.push(user(&format!(
+                "Question number {turn} of the conversation?"
+            )));
+            if turn == 2 {
+                messages.push(
+                    ChatCompletionRequestAssistantMessageArgs::default()
+                        .tool_calls(vec![ChatCompletionMessageToolCall {
+                            id: "call_1".into(),
+                            r#type: ChatCompletionToolType::Function,
+                            function: FunctionCall {
+                                name: "search".into(),
+                                arguments: r#"{"query": "answer"}"#.into(),
+                            },
+                        }])
+                        .build()
+                        .unwrap()
+                        .into(),
+                );
+                messages.push(
+                    ChatCompletionRequestToolMessageArgs::default()
+                        .tool_call_id("call_1")
+                        .content(r#"{"result": 42}"#)
+                        .build()
+                        .unwrap()
+                        .into(),
+                );
+            }
+            messages.push(assistant(&format!(
+                "Answer number {turn} of the conversation."
+            )));
+        }
+        messages
+    }
+
+    fn is_valid(messages: &[ChatCompletionRequestMessage]) -> bool {
+        messages.iter().enumerate().all(|(index, message)| {
+            !matches!(message, ChatCompletionRequestMessage::Tool(_))
+                || matches!(
+                    messages.get(index.wrapping_sub(1)),
+                    Some(ChatCompletionRequestMessage::Assistant(assistant)) if assistant.tool_calls.is_some()
+                )
+        })
+    }
+
+    #[tokio::test]
+    async fn test_truncation_strategies() {
+        let messages = conversation();
+        let window = ContextWindow::for_model("gpt-3.5-turbo", 1000).unwrap();
+        let total = window.count(&messages);
+        assert_eq!(window.fit(messages.clone()).await.unwrap(), messages);
+
+        // Drop oldest, keeping the system message and never splitting the tool call
+        for budget in (20..total).step_by(7) {
+            let window = ContextWindow::for_model("gpt-3.5-turbo", budget).unwrap();
+            let Ok(fitted) = window.fit(messages.clone()).await else {
+                continue;
+            };
+            assert!(window.count(&fitted) <= budget);
+            assert_eq!(fitted[0], messages[0]);
+            assert_eq!(fitted.last(), messages.last());
+            assert!(is_valid(&fitted), "{fitted:?}");
+        }
+        let window = ContextWindow::for_model("gpt-3.5-turbo", 10).unwrap();
+        assert!(window.fit(messages.clone()).await.is_err());
+
+        // Keep first and last turns
+        let window = ContextWindow::for_model("gpt-3.5-turbo", total - 40)
+            .unwrap()
+            .with_strategy(TruncationStrategy::KeepFirstLast { first: 2, last: 3 });
+        let fitted = window.fit(messages.clone()).await.unwrap();
+        assert_eq!(fitted[..3], messages[..3]);
+        assert_eq!(fitted[fitted.len() - 3..], messages[messages.len() - 3..]);
+        assert!(fitted.len() < messages.len());
+        assert!(is_valid(&fitted));
+
+        // Summarize dropped turns, once
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let window = ContextWindow::for_model("gpt-3.5-turbo", total / 2)
+            .unwrap()
+            .with_strategy(TruncationStrategy::summarize(move |dropped| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(format!("{} messages were dropped", dropped.len())) }
+            }));
+        let fitted = window.fit(messages.clone()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(window.count(&fitted) <= total / 2);
+        assert_eq!(fitted[0], messages[0]);
+        let ChatCompletionRequestMessage::System(summary) = &fitted[1] else {
+            panic!("summary is missing: {fitted:?}");
+        };
+        // Turns dropped to make room for the summary are not part of it
+        let dropped = messages.len() - (fitted.len() - 1);
+        let summarized: usize = summary.content.split(' ').next().unwrap().parse().unwrap();
+        assert!(summarized > 0 && summarized <= dropped);
+        assert!(is_valid(&fitted));
+
+        // Long summaries make room by dropping more turns, without summarizing again
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let window = ContextWindow::for_model("gpt-3.5-turbo", total / 2)
+            .unwrap()
+            .with_strategy(TruncationStrategy::summarize(move |dropped| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move { Ok(format!("{} messages were dropped", dropped.len()).repeat(5)) }
+            }));
+        let long_fitted = window.fit(messages.clone()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(window.count(&long_fitted) <= total / 2);
+        assert!(long_fitted.len() < fitted.len());
+        assert!(is_valid(&long_fitted));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     config::Config,
     error::OpenAIError,
+    response::Response,
     types::{CreateEmbeddingRequest, CreateEmbeddingResponse},
     Client,
 };
@@ -23,7 +24,36 @@ impl<'c, C: Config> Embeddings<'c, C> {
         &self,
         request: CreateEmbeddingRequest,
     ) -> Result<CreateEmbeddingResponse, OpenAIError> {
-        self.client.post("/embeddings", request).await
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let response: CreateEmbeddingResponse = self.client.post("/embeddings", request).await?;
+        self.client.record_usage(
+            "embeddings",
+            &model,
+            user.as_deref(),
+            (&response.usage).into(),
+        );
+        Ok(response)
+    }
+
+    /// Same as [Embeddings::create], along with the request id and rate limits from response headers.
+    pub async fn create_with_meta(
+        &self,
+        request: CreateEmbeddingRequest,
+    ) -> Result<Response<CreateEmbeddingResponse>, OpenAIError> {
+        self.client.validate(&request)?;
+        self.client.wait_for_rate_limit(&request).await;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let response: Response<CreateEmbeddingResponse> =
+            self.client.post_with_meta("/embeddings", request).await?;
+        self.client.record_usage(
+            "embeddings",
+            &model,
+            user.as_deref(),
+            (&response.data.usage).into(),
+        );
+        Ok(response)
     }
 }
 
@@ -33,6 +63,7 @@ mod tests {
     use crate::types::{CreateEmbeddingResponse, Embedding};
 
     #[tokio::test]
+    #[ignore = "calls the OpenAI API with OPENAI_API_KEY"]
     async fn test_embedding_string() {
         let client = Client::new();
 
@@ -48,6 +79,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "calls the OpenAI API with OPENAI_API_KEY"]
     async fn test_embedding_string_array() {
         let client = Client::new();
 
@@ -63,6 +95,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "calls the OpenAI API with OPENAI_API_KEY"]
     async fn test_embedding_integer_array() {
         let client = Client::new();
 
@@ -78,6 +111,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "calls the OpenAI API with OPENAI_API_KEY"]
     async fn test_embedding_array_of_integer_array_matrix() {
         let client = Client::new();
 
@@ -93,6 +127,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "calls the OpenAI API with OPENAI_API_KEY"]
     async fn test_embedding_array_of_integer_array() {
         let client = Client::new();
 
@@ -108,6 +143,7 @@ mod tests {
     }
 
     #[tokio::test]
+    #[ignore = "calls the OpenAI API with OPENAI_API_KEY"]
     async fn test_embedding_with_reduced_dimensions() {
         let client = Client::new();
         let dimensions = 256u32;
@@ -1,6 +1,11 @@
 //! Errors originating from API calls, parsing responses, and reading-or-writing to the file system.
+use std::fmt::Display;
+
+use reqwest::{header::HeaderMap, StatusCode};
 use serde::Deserialize;
 
+use crate::response::ResponseMeta;
+
 #[derive(Debug, thiserror::Error)]
 pub enum OpenAIError {
     /// Underlying error from reqwest library after an API call was made
@@ -21,6 +26,18 @@ pub enum OpenAIError {
     /// Error on SSE streaming
     #[error("stream failed: {0}")]
     StreamError(String),
+    /// Chat completion stream lost its connection before the model finished,
+    /// when opted in with [crate::stream_recovery::StreamRecovery]
+    #[error("stream interrupted: {}", .0.reason)]
+    StreamInterrupted(StreamInterrupted),
+    /// Model kept calling tools after the maximum number of iterations of [crate::Chat::create_with_tools],
+    /// holds the tool calls which were not run
+    #[error("model still calling tools after {} iterations", .0.max_iterations)]
+    ToolLoopLimit(ToolLoopLimit),
+    /// Run was still queued or in progress when [crate::Runs::poll_until_terminal] timed out,
+    /// holds the run as last retrieved, or as returned by the cancel request
+    #[error("run {} still {:?} after polling timed out", .0.id, .0.status)]
+    RunPollTimeout(Box<crate::types::RunObject>),
     /// Error from client side validation
     /// or when builder fails to build request before making API call
     #[error("invalid args: {0}")]
@@ -28,12 +45,168 @@ pub enum OpenAIError {
 }
 
 /// OpenAI API returns error object on failure
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct ApiError {
     pub message: String,
     pub r#type: Option<String>,
     pub param: Option<serde_json::Value>,
     pub code: Option<serde_json::Value>,
+    /// Status, request id and headers of the failed response.
+    #[serde(skip)]
+    pub response: Option<Box<ResponseMeta>>,
+}
+
+impl ApiError {
+    /// Attach the status and headers of the response which carried this error.
+    pub(crate) fn with_response(mut self, status: StatusCode, headers: HeaderMap) -> Self {
+        self.response = Some(Box::new(ResponseMeta::new(status, headers)));
+        self
+    }
+
+    /// HTTP status code of the failed response.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.response.as_ref().map(|response| response.status)
+    }
+
+    /// Unique identifier of the request from `x-request-id` header, useful when contacting support.
+    pub fn request_id(&self) -> Option<&str> {
+        self.response.as_ref()?.request_id.as_deref()
+    }
+
+    /// Headers of the failed response, such as `retry-after` and `x-ratelimit-*`.
+    pub fn headers(&self) -> Option<&HeaderMap> {
+        self.response.as_ref().map(|response| &response.headers)
+    }
+
+    /// The `type` field decoded into an [ApiErrorKind].
+    pub fn error_type(&self) -> Option<ApiErrorKind> {
+        self.r#type.as_deref().map(ApiErrorKind::from)
+    }
+
+    /// The `code` field decoded into an [ApiErrorKind], numeric codes are kept as [ApiErrorKind::Other].
+    pub fn error_code(&self) -> Option<ApiErrorKind> {
+        match self.code.as_ref()? {
+            serde_json::Value::String(code) => Some(ApiErrorKind::from(code.as_str())),
+            serde_json::Value::Null => None,
+            code => Some(ApiErrorKind::Other(code.to_string())),
+        }
+    }
+
+    /// The most specific kind of this error: its code when present, otherwise its type.
+    ///
+    /// ```
+    /// use async_openai::error::{ApiError, ApiErrorKind};
+    ///
+    /// let error: ApiError = serde_json::from_str(r#"{
+    ///     "message": "This model's maximum context length is 8192 tokens.",
+    ///     "type": "invalid_request_error",
+    ///     "param": "messages",
+    ///     "code": "context_length_exceeded"
+    /// }"#).unwrap();
+    ///
+    /// assert_eq!(error.kind(), Some(ApiErrorKind::ContextLengthExceeded));
+    /// assert_eq!(error.error_type(), Some(ApiErrorKind::InvalidRequestError));
+    /// ```
+    pub fn kind(&self) -> Option<ApiErrorKind> {
+        self.error_code().or_else(|| self.error_type())
+    }
+}
+
+/// Known values of the `type` and `code` fields of [ApiError].
+///
+/// New values may be returned by the API at any time, those are kept as [ApiErrorKind::Other].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ApiErrorKind {
+    /// `invalid_request_error`
+    InvalidRequestError,
+    /// `authentication_error`
+    AuthenticationError,
+    /// `permission_error`
+    PermissionError,
+    /// `not_found_error`
+    NotFoundError,
+    /// `invalid_api_key`
+    InvalidApiKey,
+    /// `rate_limit_exceeded`
+    RateLimitExceeded,
+    /// `insufficient_quota`
+    InsufficientQuota,
+    /// `context_length_exceeded`
+    ContextLengthExceeded,
+    /// `model_not_found`
+    ModelNotFound,
+    /// `server_error`
+    ServerError,
+    /// `engine_overloaded`
+    EngineOverloaded,
+    /// Any other value, as returned by the API.
+    Other(String),
+}
+
+impl ApiErrorKind {
+    /// Value of this kind as returned by the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::InvalidRequestError => "invalid_request_error",
+            Self::AuthenticationError => "authentication_error",
+            Self::PermissionError => "permission_error",
+            Self::NotFoundError => "not_found_error",
+            Self::InvalidApiKey => "invalid_api_key",
+            Self::RateLimitExceeded => "rate_limit_exceeded",
+            Self::InsufficientQuota => "insufficient_quota",
+            Self::ContextLengthExceeded => "context_length_exceeded",
+            Self::ModelNotFound => "model_not_found",
+            Self::ServerError => "server_error",
+            Self::EngineOverloaded => "engine_overloaded",
+            Self::Other(value) => value,
+        }
+    }
+}
+
+impl From<&str> for ApiErrorKind {
+    fn from(value: &str) -> Self {
+        match value {
+            "invalid_request_error" => Self::InvalidRequestError,
+            "authentication_error" => Self::AuthenticationError,
+            "permission_error" => Self::PermissionError,
+            "not_found_error" => Self::NotFoundError,
+            "invalid_api_key" => Self::InvalidApiKey,
+            "rate_limit_exceeded" => Self::RateLimitExceeded,
+            "insufficient_quota" => Self::InsufficientQuota,
+            "context_length_exceeded" => Self::ContextLengthExceeded,
+            "model_not_found" => Self::ModelNotFound,
+            "server_error" => Self::ServerError,
+            "engine_overloaded" => Self::EngineOverloaded,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl Display for ApiErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Content received before a stream was interrupted, it can be used to resume or report a partial answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamInterrupted {
+    /// Description of the transport failure.
+    pub reason: String,
+    /// Text content received so far for each choice, indexed by choice index.
+    pub partial_content: Vec<String>,
+}
+
+/// Tool calls left when [crate::Chat::create_with_tools] reached its maximum number of iterations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolLoopLimit {
+    /// Maximum number of responses which was reached.
+    pub max_iterations: u32,
+    /// Tool calls of the last response, which were not run.
+    pub tool_calls: Vec<crate::types::ChatCompletionMessageToolCall>,
+    /// Messages of the request, followed by the tool calls and their results, ending with the last response.
+    pub messages: Vec<crate::types::ChatCompletionRequestMessage>,
 }
 
 /// Wrapper to deserialize the error object nested in "error" JSON key
@@ -3,7 +3,7 @@ use serde::Serialize;
 use crate::{
     config::Config,
     error::OpenAIError,
-    types::{CreateFileRequest, DeleteFileResponse, ListFilesResponse, OpenAIFile},
+    types::{CreateFileRequest, DeleteFileResponse, ListFilesQuery, ListFilesResponse, OpenAIFile},
     Client,
 };
 
@@ -27,6 +27,8 @@ impl<'c, C: Config> Files<'c, C> {
     }
 
     /// Returns a list of files that belong to the user's organization.
+    ///
+    /// `query` is usually a [ListFilesQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list<Q>(&self, query: &Q) -> Result<ListFilesResponse, OpenAIError>
     where
         Q: Serialize + ?Sized,
@@ -34,6 +36,14 @@ impl<'c, C: Config> Files<'c, C> {
         self.client.get_with_query("/files", query).await
     }
 
+    /// Same as [Files::list], only taking a [ListFilesQuery] so that mistakes in the query don't compile.
+    pub async fn list_typed(
+        &self,
+        query: &ListFilesQuery,
+    ) -> Result<ListFilesResponse, OpenAIError> {
+        self.list(query).await
+    }
+
     /// Returns information about a specific file.
     pub async fn retrieve(&self, file_id: &str) -> Result<OpenAIFile, OpenAIError> {
         self.client.get(format!("/files/{file_id}").as_str()).await
@@ -56,9 +66,14 @@ impl<'c, C: Config> Files<'c, C> {
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        testing::{MockResponse, MockServer},
+        types::{ListFilesQueryArgs, OpenAIFilePurpose},
+    };
     use crate::{types::CreateFileRequestArgs, Client};
 
     #[tokio::test]
+    #[ignore = "calls the OpenAI API with OPENAI_API_KEY"]
     async fn test_file_mod() {
         let test_file_path = "/tmp/test.jsonl";
         let contents = concat!(
@@ -112,4 +127,32 @@ mod tests {
         assert_eq!(openai_file.id, delete_response.id);
         assert!(delete_response.deleted);
     }
+
+    #[tokio::test]
+    async fn test_list_files_query() {
+        let server = MockServer::start().await;
+        server.mock(
+            "GET",
+            "/files",
+            MockResponse::json(&serde_json::json!({"object": "list", "data": []})),
+        );
+        let client = server.client();
+
+        let query = ListFilesQueryArgs::default()
+            .purpose(OpenAIFilePurpose::FineTune)
+            .build()
+            .unwrap();
+        let list_files = client.files().list_typed(&query).await.unwrap();
+        assert!(list_files.data.is_empty());
+
+        let query = ListFilesQueryArgs::default().build().unwrap();
+        client.files().list_typed(&query).await.unwrap();
+
+        let queries: Vec<_> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.query)
+            .collect();
+        assert_eq!(queries, [Some("purpose=fine-tune".to_string()), None]);
+    }
 }
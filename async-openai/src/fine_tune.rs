@@ -68,12 +68,11 @@ impl<'c, C: Config> FineTunes<'c, C> {
         &self,
         fine_tune_id: &str,
     ) -> Result<FineTuneEventsResponseStream, OpenAIError> {
-        Ok(self
-            .client
+        self.client
             .get_stream(
                 format!("/fine-tunes/{fine_tune_id}/events").as_str(),
                 &[("stream", true)],
             )
-            .await)
+            .await
     }
 }
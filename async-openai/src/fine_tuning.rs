@@ -1,10 +1,13 @@
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
     config::Config,
     error::OpenAIError,
+    pagination::paginate,
     types::{
-        CreateFineTuningJobRequest, FineTuningJob, ListFineTuningJobEventsResponse,
+        CreateFineTuningJobRequest, FineTuningJob, FineTuningJobEvent,
+        ListFineTuningJobEventsQuery, ListFineTuningJobEventsResponse, ListFineTuningJobsQuery,
         ListPaginatedFineTuningJobsResponse,
     },
     Client,
@@ -31,10 +34,13 @@ impl<'c, C: Config> FineTuning<'c, C> {
         &self,
         request: CreateFineTuningJobRequest,
     ) -> Result<FineTuningJob, OpenAIError> {
+        self.client.validate(&request)?;
         self.client.post("/fine_tuning/jobs", request).await
     }
 
     /// List your organization's fine-tuning jobs
+    ///
+    /// `query` is usually a [ListFineTuningJobsQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list_paginated<Q>(
         &self,
         query: &Q,
@@ -45,6 +51,26 @@ impl<'c, C: Config> FineTuning<'c, C> {
         self.client.get_with_query("/fine_tuning/jobs", query).await
     }
 
+    /// Same as [FineTuning::list_paginated], only taking a [ListFineTuningJobsQuery] so that mistakes in the query don't compile.
+    pub async fn list_paginated_typed(
+        &self,
+        query: &ListFineTuningJobsQuery,
+    ) -> Result<ListPaginatedFineTuningJobsResponse, OpenAIError> {
+        self.list_paginated(query).await
+    }
+
+    /// Returns every fine-tuning job of your organization, fetching pages as the stream is polled.
+    pub fn list_all(
+        &self,
+        query: ListFineTuningJobsQuery,
+    ) -> impl Stream<Item = Result<FineTuningJob, OpenAIError>> + 'c {
+        paginate::<C, ListPaginatedFineTuningJobsResponse>(
+            self.client,
+            "/fine_tuning/jobs".to_string(),
+            query.into(),
+        )
+    }
+
     /// Gets info about the fine-tune job.
     ///
     /// [Learn more about Fine-tuning](https://platform.openai.com/docs/guides/fine-tuning)
@@ -65,6 +91,8 @@ impl<'c, C: Config> FineTuning<'c, C> {
     }
 
     /// Get fine-grained status updates for a fine-tune job.
+    ///
+    /// `query` is usually a [ListFineTuningJobEventsQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list_events<Q>(
         &self,
         fine_tuning_job_id: &str,
@@ -80,4 +108,26 @@ impl<'c, C: Config> FineTuning<'c, C> {
             )
             .await
     }
+
+    /// Same as [FineTuning::list_events], only taking a [ListFineTuningJobEventsQuery] so that mistakes in the query don't compile.
+    pub async fn list_events_typed(
+        &self,
+        fine_tuning_job_id: &str,
+        query: &ListFineTuningJobEventsQuery,
+    ) -> Result<ListFineTuningJobEventsResponse, OpenAIError> {
+        self.list_events(fine_tuning_job_id, query).await
+    }
+
+    /// Returns every event of a fine-tuning job, fetching pages as the stream is polled.
+    pub fn list_all_events(
+        &self,
+        fine_tuning_job_id: &str,
+        query: ListFineTuningJobEventsQuery,
+    ) -> impl Stream<Item = Result<FineTuningJobEvent, OpenAIError>> + 'c {
+        paginate::<C, ListFineTuningJobEventsResponse>(
+            self.client,
+            format!("/fine_tuning/jobs/{fine_tuning_job_id}/events"),
+            query.into(),
+        )
+    }
 }
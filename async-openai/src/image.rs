@@ -1,9 +1,12 @@
 use crate::{
     config::Config,
     error::OpenAIError,
+    response::Response,
     types::{
-        CreateImageEditRequest, CreateImageRequest, CreateImageVariationRequest, ImagesResponse,
+        CreateImageEditRequest, CreateImageRequest, CreateImageVariationRequest, ImageModel,
+        ImageQuality, ImageSize, ImagesResponse,
     },
+    usage::UsageTotals,
     Client,
 };
 
@@ -21,7 +24,28 @@ impl<'c, C: Config> Images<'c, C> {
 
     /// Creates an image given a prompt.
     pub async fn create(&self, request: CreateImageRequest) -> Result<ImagesResponse, OpenAIError> {
-        self.client.post("/images/generations", request).await
+        self.client.validate(&request)?;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let (size, quality) = (request.size, request.quality.clone());
+        let response: ImagesResponse = self.client.post("/images/generations", request).await?;
+        self.record_usage(model, size, quality, user.as_deref(), &response);
+        Ok(response)
+    }
+
+    /// Same as [Images::create], along with the request id and rate limits from response headers.
+    pub async fn create_with_meta(
+        &self,
+        request: CreateImageRequest,
+    ) -> Result<Response<ImagesResponse>, OpenAIError> {
+        self.client.validate(&request)?;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let (size, quality) = (request.size, request.quality.clone());
+        let response: Response<ImagesResponse> = self
+            .client
+            .post_with_meta("/images/generations", request)
+            .await?;
+        self.record_usage(model, size, quality, user.as_deref(), &response.data);
+        Ok(response)
     }
 
     /// Creates an edited or extended image given an original image and a prompt.
@@ -29,7 +53,12 @@ impl<'c, C: Config> Images<'c, C> {
         &self,
         request: CreateImageEditRequest,
     ) -> Result<ImagesResponse, OpenAIError> {
-        self.client.post_form("/images/edits", request).await
+        self.client.validate(&request)?;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let size = request.size.map(ImageSize::from);
+        let response: ImagesResponse = self.client.post_form("/images/edits", request).await?;
+        self.record_usage(model, size, None, user.as_deref(), &response);
+        Ok(response)
     }
 
     /// Creates a variation of a given image.
@@ -37,6 +66,42 @@ impl<'c, C: Config> Images<'c, C> {
         &self,
         request: CreateImageVariationRequest,
     ) -> Result<ImagesResponse, OpenAIError> {
-        self.client.post_form("/images/variations", request).await
+        self.client.validate(&request)?;
+        let (model, user) = (request.model.clone(), request.user.clone());
+        let size = request.size.map(ImageSize::from);
+        let response: ImagesResponse = self.client.post_form("/images/variations", request).await?;
+        self.record_usage(model, size, None, user.as_deref(), &response);
+        Ok(response)
+    }
+
+    /// Record the images of `response`, priced by their size and quality in the catalog of the usage tracker.
+    fn record_usage(
+        &self,
+        model: Option<ImageModel>,
+        size: Option<ImageSize>,
+        quality: Option<ImageQuality>,
+        user: Option<&str>,
+        response: &ImagesResponse,
+    ) {
+        let Some(usage_tracker) = self.client.usage_tracker() else {
+            return;
+        };
+        let model = model.unwrap_or_default().to_string();
+        let images = response.data.len() as u64;
+        let cost = usage_tracker
+            .catalog()
+            .get(&model)
+            .and_then(|info| info.pricing.as_ref())
+            .and_then(|pricing| {
+                pricing.image_price(size.unwrap_or_default(), &quality.unwrap_or_default())
+            })
+            .map(|per_image| images as f64 * per_image);
+
+        let usage = UsageTotals {
+            images,
+            cost,
+            ..Default::default()
+        };
+        self.client.record_usage("images", &model, user, usage);
     }
 }
@@ -45,7 +45,7 @@
 //!
 //! ## Making requests
 //!
-//!```
+//!```no_run
 //!# tokio_test::block_on(async {
 //!
 //! use async_openai::{Client, types::{CreateCompletionRequestArgs}};
@@ -76,12 +76,16 @@
 //! ## Examples
 //! For full working examples for all supported features see [examples](https://github.com/64bit/async-openai/tree/main/examples) directory in the repository.
 //!
+pub mod aggregate;
 mod assistant_files;
 mod assistants;
 mod audio;
+pub mod catalog;
 mod chat;
 mod client;
 mod completion;
+#[cfg(feature = "tokenizer")]
+pub mod context;
 pub mod config;
 mod download;
 #[deprecated(since = "0.15.0", note = "By OpenAI")]
@@ -95,13 +99,27 @@ mod fine_tuning;
 mod image;
 mod message_files;
 mod messages;
+pub mod middleware;
 mod model;
 mod moderation;
+mod pagination;
+pub mod partial_json;
+pub mod rate_limiter;
+pub mod response;
+pub mod retry;
 mod runs;
 mod steps;
+pub mod stream_recovery;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod threads;
+#[cfg(feature = "tokenizer")]
+pub mod tokenizer;
+pub mod tools;
 pub mod types;
+pub mod usage;
 mod util;
+pub mod validate;
 
 pub use assistant_files::AssistantFiles;
 pub use assistants::Assistants;
@@ -119,6 +137,6 @@ pub use message_files::MessageFiles;
 pub use messages::Messages;
 pub use model::Models;
 pub use moderation::Moderations;
-pub use runs::Runs;
+pub use runs::{PollOptions, Runs};
 pub use steps::Steps;
 pub use threads::Threads;
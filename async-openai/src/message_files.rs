@@ -1,9 +1,11 @@
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
     config::Config,
     error::OpenAIError,
-    types::{ListMessageFilesResponse, MessageFileObject},
+    pagination::paginate,
+    types::{ListMessageFilesQuery, ListMessageFilesResponse, MessageFileObject},
     Client,
 };
 
@@ -34,6 +36,8 @@ impl<'c, C: Config> MessageFiles<'c, C> {
     }
 
     /// Returns a list of message files.
+    ///
+    /// `query` is usually a [ListMessageFilesQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list<Q>(&self, query: &Q) -> Result<ListMessageFilesResponse, OpenAIError>
     where
         Q: Serialize + ?Sized,
@@ -48,4 +52,27 @@ impl<'c, C: Config> MessageFiles<'c, C> {
             )
             .await
     }
+
+    /// Same as [MessageFiles::list], only taking a [ListMessageFilesQuery] so that mistakes in the query don't compile.
+    pub async fn list_typed(
+        &self,
+        query: &ListMessageFilesQuery,
+    ) -> Result<ListMessageFilesResponse, OpenAIError> {
+        self.list(query).await
+    }
+
+    /// Returns all files of the message, fetching pages of `limit` objects as the stream is polled.
+    pub fn list_all(
+        &self,
+        query: ListMessageFilesQuery,
+    ) -> impl Stream<Item = Result<MessageFileObject, OpenAIError>> + 'c {
+        paginate::<C, ListMessageFilesResponse>(
+            self.client,
+            format!(
+                "/threads/{}/messages/{}/files",
+                self.thread_id, self.message_id
+            ),
+            query,
+        )
+    }
 }
@@ -1,9 +1,14 @@
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
     config::Config,
     error::OpenAIError,
-    types::{CreateMessageRequest, ListMessagesResponse, MessageObject, ModifyMessageRequest},
+    pagination::paginate,
+    types::{
+        CreateMessageRequest, ListMessagesQuery, ListMessagesResponse, MessageObject,
+        ModifyMessageRequest,
+    },
     Client, MessageFiles,
 };
 
@@ -32,6 +37,7 @@ impl<'c, C: Config> Messages<'c, C> {
         &self,
         request: CreateMessageRequest,
     ) -> Result<MessageObject, OpenAIError> {
+        self.client.validate(&request)?;
         self.client
             .post(&format!("/threads/{}/messages", self.thread_id), request)
             .await
@@ -62,6 +68,8 @@ impl<'c, C: Config> Messages<'c, C> {
     }
 
     /// Returns a list of messages for a given thread.
+    ///
+    /// `query` is usually a [ListMessagesQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list<Q>(&self, query: &Q) -> Result<ListMessagesResponse, OpenAIError>
     where
         Q: Serialize + ?Sized,
@@ -70,4 +78,24 @@ impl<'c, C: Config> Messages<'c, C> {
             .get_with_query(&format!("/threads/{}/messages", self.thread_id), query)
             .await
     }
+
+    /// Same as [Messages::list], only taking a [ListMessagesQuery] so that mistakes in the query don't compile.
+    pub async fn list_typed(
+        &self,
+        query: &ListMessagesQuery,
+    ) -> Result<ListMessagesResponse, OpenAIError> {
+        self.list(query).await
+    }
+
+    /// Returns all messages of the thread, fetching pages of `limit` objects as the stream is polled.
+    pub fn list_all(
+        &self,
+        query: ListMessagesQuery,
+    ) -> impl Stream<Item = Result<MessageObject, OpenAIError>> + 'c {
+        paginate::<C, ListMessagesResponse>(
+            self.client,
+            format!("/threads/{}/messages", self.thread_id),
+            query,
+        )
+    }
 }
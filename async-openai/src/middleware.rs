@@ -0,0 +1,151 @@
+//! Hooks to observe and modify HTTP requests made by [crate::Client].
+use std::{fmt, sync::Arc};
+
+use crate::error::OpenAIError;
+
+/// Re-exported so that [Middleware] can be implemented without depending on `async-trait` directly.
+pub use async_convert::async_trait;
+
+/// A middleware is invoked around every HTTP request made by [crate::Client]:
+/// JSON requests, multipart form uploads, raw byte responses and SSE streams alike.
+///
+/// All hooks have no-op default implementations, so implementors only override what they need.
+/// Middlewares are stacked in the order they are added with [crate::Client::with_middleware]:
+/// `before_request` runs first-to-last, `after_response` and `on_error` run last-to-first.
+///
+/// ```
+/// use async_openai::{error::OpenAIError, middleware::{async_trait, Middleware}, Client};
+///
+/// struct LogRequests;
+///
+/// #[async_trait]
+/// impl Middleware for LogRequests {
+///     async fn before_request(&self, request: &mut reqwest::Request) -> Result<(), OpenAIError> {
+///         println!("{} {}", request.method(), request.url());
+///         Ok(())
+///     }
+/// }
+///
+/// let client = Client::new().with_middleware(LogRequests);
+/// ```
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Called before every attempt to send `request`, including retries.
+    /// Headers, url and body can be modified in place, for example to sign the request or refresh credentials.
+    ///
+    /// Returning an error aborts the API call without retrying.
+    async fn before_request(&self, _request: &mut reqwest::Request) -> Result<(), OpenAIError> {
+        Ok(())
+    }
+
+    /// Called once the status and headers of a response are received, before its body is read.
+    /// For SSE requests this is the response which opens the event stream.
+    ///
+    /// Returning an error aborts the API call without retrying.
+    async fn after_response(&self, _response: &reqwest::Response) -> Result<(), OpenAIError> {
+        Ok(())
+    }
+
+    /// Called for every failed attempt, including the ones which are retried
+    /// and errors received while reading an SSE stream.
+    async fn on_error(&self, _error: &OpenAIError) {}
+}
+
+/// Ordered list of [Middleware]s shared by all clones of a [crate::Client].
+#[derive(Clone, Default)]
+pub(crate) struct MiddlewareStack {
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl fmt::Debug for MiddlewareStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiddlewareStack")
+            .field("len", &self.middlewares.len())
+            .finish()
+    }
+}
+
+impl MiddlewareStack {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middlewares.push(middleware);
+    }
+
+    pub(crate) async fn before_request(
+        &self,
+        request: &mut reqwest::Request,
+    ) -> Result<(), OpenAIError> {
+        for middleware in self.middlewares.iter() {
+            middleware.before_request(request).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn after_response(
+        &self,
+        response: &reqwest::Response,
+    ) -> Result<(), OpenAIError> {
+        for middleware in self.middlewares.iter().rev() {
+            middleware.after_response(response).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn on_error(&self, error: &OpenAIError) {
+        for middleware in self.middlewares.iter().rev() {
+            middleware.on_error(error).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::{async_trait, Middleware, MiddlewareStack};
+    use crate::error::OpenAIError;
+
+    struct Record(&'static str, Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl Middleware for Record {
+        async fn before_request(&self, request: &mut reqwest::Request) -> Result<(), OpenAIError> {
+            request
+                .headers_mut()
+                .insert("x-middleware", self.0.parse().unwrap());
+            self.1.lock().unwrap().push(format!("before {}", self.0));
+            Ok(())
+        }
+
+        async fn on_error(&self, _error: &OpenAIError) {
+            self.1.lock().unwrap().push(format!("error {}", self.0));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_order() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let mut stack = MiddlewareStack::default();
+        stack.push(Arc::new(Record("first", log.clone())));
+        stack.push(Arc::new(Record("second", log.clone())));
+
+        let mut request = reqwest::Client::new()
+            .get("http://localhost/v1/models")
+            .build()
+            .unwrap();
+        stack.before_request(&mut request).await.unwrap();
+        stack
+            .on_error(&OpenAIError::StreamError("closed".into()))
+            .await;
+
+        assert_eq!(request.headers()["x-middleware"], "second");
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "before first",
+                "before second",
+                "error second",
+                "error first"
+            ]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     config::Config,
     error::OpenAIError,
+    response::Response,
     types::{CreateModerationRequest, CreateModerationResponse},
     Client,
 };
@@ -24,4 +25,12 @@ impl<'c, C: Config> Moderations<'c, C> {
     ) -> Result<CreateModerationResponse, OpenAIError> {
         self.client.post("/moderations", request).await
     }
+
+    /// Same as [Moderations::create], along with the request id and rate limits from response headers.
+    pub async fn create_with_meta(
+        &self,
+        request: CreateModerationRequest,
+    ) -> Result<Response<CreateModerationResponse>, OpenAIError> {
+        self.client.post_with_meta("/moderations", request).await
+    }
 }
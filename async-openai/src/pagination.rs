@@ -0,0 +1,274 @@
+//! Lazy iteration over every object of cursor-paginated list endpoints.
+use futures::{stream, Stream};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    config::Config,
+    error::OpenAIError,
+    types::{
+        AssistantFileObject, AssistantObject, FineTuningJob, FineTuningJobEvent,
+        ListAssistantFilesResponse, ListAssistantsResponse, ListFineTuningJobEventsResponse,
+        ListMessageFilesResponse, ListMessagesResponse, ListPaginatedFineTuningJobsResponse,
+        ListQuery, ListRunStepsResponse, ListRunsResponse, MessageFileObject, MessageObject,
+        RunObject, RunStepObject,
+    },
+    Client,
+};
+
+/// A page of a cursor-paginated list response.
+pub(crate) trait Page: DeserializeOwned {
+    type Item;
+
+    /// Whether there are more objects after this page.
+    fn has_more(&self) -> bool;
+    /// Cursor to the first object of this page, for the `before` of the previous page.
+    fn first_id(&self) -> Option<String>;
+    /// Cursor to the last object of this page, for the `after` of the next page.
+    fn last_id(&self) -> Option<String>;
+    fn into_items(self) -> Vec<Self::Item>;
+}
+
+/// Implements [Page] for list responses with `first_id` and `last_id` cursors.
+macro_rules! page_with_cursors {
+    ($($response:ty => $item:ty),* $(,)?) => {
+        $(
+            impl Page for $response {
+                type Item = $item;
+
+                fn has_more(&self) -> bool {
+                    self.has_more
+                }
+
+                fn first_id(&self) -> Option<String> {
+                    self.first_id.clone()
+                }
+
+                fn last_id(&self) -> Option<String> {
+                    self.last_id.clone()
+                }
+
+                fn into_items(self) -> Vec<Self::Item> {
+                    self.data
+                }
+            }
+        )*
+    };
+}
+
+page_with_cursors!(
+    ListAssistantsResponse => AssistantObject,
+    ListAssistantFilesResponse => AssistantFileObject,
+    ListMessagesResponse => MessageObject,
+    ListMessageFilesResponse => MessageFileObject,
+    ListRunsResponse => RunObject,
+    ListRunStepsResponse => RunStepObject,
+);
+
+/// Implements [Page] for list responses without cursors, which are taken from the ids of the objects.
+macro_rules! page_with_ids {
+    ($($response:ty => $item:ty),* $(,)?) => {
+        $(
+            impl Page for $response {
+                type Item = $item;
+
+                fn has_more(&self) -> bool {
+                    self.has_more
+                }
+
+                fn first_id(&self) -> Option<String> {
+                    self.data.first().map(|item| item.id.clone())
+                }
+
+                fn last_id(&self) -> Option<String> {
+                    self.data.last().map(|item| item.id.clone())
+                }
+
+                fn into_items(self) -> Vec<Self::Item> {
+                    self.data
+                }
+            }
+        )*
+    };
+}
+
+page_with_ids!(
+    ListPaginatedFineTuningJobsResponse => FineTuningJob,
+    ListFineTuningJobEventsResponse => FineTuningJobEvent,
+);
+
+struct PaginationState<'c, C: Config, T> {
+    client: &'c Client<C>,
+    path: String,
+    /// Query of the next page, `None` once the last page was fetched.
+    query: Option<ListQuery>,
+    items: std::vec::IntoIter<T>,
+}
+
+/// Stream of every object of the list endpoint at `path`, starting from `query`.
+///
+/// Pages are fetched as the stream is polled. When only `before` is set the list is walked backwards,
+/// otherwise forwards with `after`. The stream ends after the first error.
+pub(crate) fn paginate<'c, C, P>(
+    client: &'c Client<C>,
+    path: String,
+    query: ListQuery,
+) -> impl Stream<Item = Result<P::Item, OpenAIError>> + 'c
+where
+    C: Config,
+    P: Page + 'c,
+{
+    let state = PaginationState {
+        client,
+        path,
+        query: Some(query),
+        items: Vec::new().into_iter(),
+    };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.items.next() {
+                return Some((Ok(item), state));
+            }
+
+            let query = state.query.take()?;
+            let page: P = match state.client.get_with_query(&state.path, &query).await {
+                Ok(page) => page,
+                Err(e) => return Some((Err(e), state)),
+            };
+
+            if page.has_more() {
+                state.query = if query.before.is_some() && query.after.is_none() {
+                    page.first_id().map(|before| ListQuery {
+                        before: Some(before),
+                        ..query
+                    })
+                } else {
+                    page.last_id().map(|after| ListQuery {
+                        after: Some(after),
+                        ..query
+                    })
+                };
+            }
+            state.items = page.into_items().into_iter();
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use serde_json::json;
+
+    use crate::{
+        testing::{MockResponse, MockServer},
+        types::{ListFineTuningJobEventsQueryArgs, ListOrder, ListQueryArgs},
+    };
+
+    fn assistants(ids: &[&str], has_more: bool) -> MockResponse {
+        let data: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                json!({
+                    "id": id,
+                    "object": "assistant",
+                    "created_at": 0,
+                    "name": null,
+                    "description": null,
+                    "model": "gpt-4",
+                    "instructions": null,
+                    "tools": [],
+                    "file_ids": [],
+                    "metadata": null
+                })
+            })
+            .collect();
+        MockResponse::json(&json!({
+            "object": "list",
+            "data": data,
+            "first_id": ids.first(),
+            "last_id": ids.last(),
+            "has_more": has_more
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_list_all() {
+        let server = MockServer::start().await;
+        server
+            .mock(
+                "GET",
+                "/assistants",
+                assistants(&["asst_1", "asst_2"], true),
+            )
+            .mock("GET", "/assistants", assistants(&["asst_3"], false));
+        let client = server.client();
+
+        let query = ListQueryArgs::default()
+            .limit(2)
+            .order(ListOrder::Asc)
+            .build()
+            .unwrap();
+        let ids: Vec<String> = client
+            .assistants()
+            .list_all(query)
+            .map_ok(|assistant| assistant.id)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(ids, ["asst_1", "asst_2", "asst_3"]);
+
+        let queries: Vec<_> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.query.unwrap_or_default())
+            .collect();
+        assert_eq!(
+            queries,
+            ["limit=2&order=asc", "limit=2&order=asc&after=asst_2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_all_without_cursors() {
+        let server = MockServer::start().await;
+        let events = |ids: &[&str], has_more: bool| {
+            let data: Vec<_> = ids
+                .iter()
+                .map(|id| {
+                    json!({
+                        "id": id,
+                        "object": "fine_tuning.job.event",
+                        "created_at": 0,
+                        "level": "info",
+                        "message": "Step 1/10"
+                    })
+                })
+                .collect();
+            MockResponse::json(&json!({"object": "list", "data": data, "has_more": has_more}))
+        };
+        server
+            .mock("GET", "/fine_tuning/jobs/*/events", events(&["ev_1"], true))
+            .mock(
+                "GET",
+                "/fine_tuning/jobs/*/events",
+                events(&["ev_2"], false),
+            );
+        let client = server.client();
+
+        let query = ListFineTuningJobEventsQueryArgs::default()
+            .limit(1)
+            .build()
+            .unwrap();
+        let events: Vec<_> = client
+            .fine_tuning()
+            .list_all_events("ftjob-1", query)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+
+        let requests = server.requests();
+        assert_eq!(requests[1].path, "/fine_tuning/jobs/ftjob-1/events");
+        assert_eq!(requests[1].query.as_deref(), Some("limit=1&after=ev_1"));
+    }
+}
@@ -0,0 +1,364 @@
+//! Best-effort parsing of JSON which is still being generated, such as streamed tool call arguments.
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::error::{map_deserialization_error, OpenAIError};
+
+/// Accumulates fragments of a JSON document, for example [crate::types::FunctionCallStream::arguments],
+/// and parses snapshots of it while it is incomplete.
+///
+/// Snapshots keep everything which is complete so far: strings being generated are cut at the last character received,
+/// and keys without a value yet, incomplete literals and dangling escapes are left out.
+///
+/// ```
+/// use async_openai::partial_json::PartialJson;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct WriteFile {
+///     path: String,
+///     content: Option<String>,
+/// }
+///
+/// let mut arguments = PartialJson::new();
+/// arguments.push(r#"{"path": "src/main.rs", "content": "fn mai"#);
+///
+/// let snapshot: WriteFile = arguments.snapshot().unwrap();
+/// assert_eq!(snapshot.content.as_deref(), Some("fn mai"));
+///
+/// arguments.push(r#"n() {}"}"#);
+/// let args: WriteFile = arguments.finish().unwrap();
+/// assert_eq!(args.content.as_deref(), Some("fn main() {}"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PartialJson {
+    buffer: String,
+}
+
+impl PartialJson {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Append the next fragment of the document.
+    pub fn push(&mut self, fragment: &str) {
+        self.buffer.push_str(fragment);
+    }
+
+    /// The document received so far.
+    pub fn as_str(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Best-effort value of the document received so far, see [parse_partial].
+    pub fn value(&self) -> Option<Value> {
+        parse_partial(&self.buffer)
+    }
+
+    /// Best-effort snapshot of the document received so far, `None` when it doesn't deserialize into `T` yet.
+    /// Fields which are generated last are usually missing, they can be declared as `Option` or `#[serde(default)]`.
+    pub fn snapshot<T: DeserializeOwned>(&self) -> Option<T> {
+        serde_json::from_value(self.value()?).ok()
+    }
+
+    /// Strictly deserialize the complete document.
+    pub fn finish<T: DeserializeOwned>(&self) -> Result<T, OpenAIError> {
+        serde_json::from_str(&self.buffer)
+            .map_err(|e| map_deserialization_error(e, self.buffer.as_bytes()))
+    }
+}
+
+/// Parse a JSON document which may be cut off at any point.
+///
+/// Returns `None` when nothing is complete yet, when the document is not valid JSON,
+/// or when arrays and objects are nested more than 128 levels deep, the same limit as [serde_json].
+pub fn parse_partial(input: &str) -> Option<Value> {
+    let mut parser = Parser {
+        input: input.as_bytes(),
+        position: 0,
+        depth: 0,
+    };
+    let value = match parser.value() {
+        Ok(Parsed::Complete(value)) => Some(value),
+        Ok(Parsed::Partial(value)) => return value,
+        Err(()) => return None,
+    };
+
+    // Only whitespace may follow the document
+    parser.whitespace();
+    if parser.position < parser.input.len() {
+        return None;
+    }
+    value
+}
+
+enum Parsed {
+    /// Value ended before the end of the input.
+    Complete(Value),
+    /// End of the input was reached while parsing the value, with what was complete so far.
+    Partial(Option<Value>),
+}
+
+/// Lenient recursive descent parser; syntax errors are `Err(())`, truncation is [Parsed::Partial].
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    /// Arrays and objects currently open.
+    depth: usize,
+}
+
+/// Deepest nesting of arrays and objects, which bounds the recursion of the parser.
+const MAX_DEPTH: usize = 128;
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.position += 1;
+        }
+    }
+
+    fn value(&mut self) -> Result<Parsed, ()> {
+        self.whitespace();
+        match self.peek() {
+            None => Ok(Parsed::Partial(None)),
+            Some(b'{' | b'[') if self.depth >= MAX_DEPTH => Err(()),
+            Some(b'{') => {
+                self.depth += 1;
+                let object = self.object();
+                self.depth -= 1;
+                object
+            }
+            Some(b'[') => {
+                self.depth += 1;
+                let array = self.array();
+                self.depth -= 1;
+                array
+            }
+            Some(b'"') => self.string(),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(()),
+        }
+    }
+
+    fn object(&mut self) -> Result<Parsed, ()> {
+        self.position += 1;
+        let mut object = Map::new();
+
+        loop {
+            self.whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Partial(Some(Value::Object(object)))),
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Parsed::Complete(Value::Object(object)));
+                }
+                Some(b'"') => {}
+                Some(_) => return Err(()),
+            }
+
+            // Key without its value is left out
+            let key = match self.string()? {
+                Parsed::Complete(Value::String(key)) => key,
+                _ => return Ok(Parsed::Partial(Some(Value::Object(object)))),
+            };
+            self.whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Partial(Some(Value::Object(object)))),
+                Some(b':') => self.position += 1,
+                Some(_) => return Err(()),
+            }
+
+            match self.value()? {
+                Parsed::Complete(value) => {
+                    object.insert(key, value);
+                }
+                Parsed::Partial(value) => {
+                    if let Some(value) = value {
+                        object.insert(key, value);
+                    }
+                    return Ok(Parsed::Partial(Some(Value::Object(object))));
+                }
+            }
+
+            self.whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Partial(Some(Value::Object(object)))),
+                Some(b',') => self.position += 1,
+                Some(b'}') => {}
+                Some(_) => return Err(()),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Parsed, ()> {
+        self.position += 1;
+        let mut array = vec![];
+
+        loop {
+            self.whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Partial(Some(Value::Array(array)))),
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Parsed::Complete(Value::Array(array)));
+                }
+                Some(_) => {}
+            }
+
+            match self.value()? {
+                Parsed::Complete(value) => array.push(value),
+                Parsed::Partial(value) => {
+                    array.extend(value);
+                    return Ok(Parsed::Partial(Some(Value::Array(array))));
+                }
+            }
+
+            self.whitespace();
+            match self.peek() {
+                None => return Ok(Parsed::Partial(Some(Value::Array(array)))),
+                Some(b',') => self.position += 1,
+                Some(b']') => {}
+                Some(_) => return Err(()),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<Parsed, ()> {
+        let start = self.position;
+        self.position += 1;
+
+        let mut escaped = false;
+        while let Some(byte) = self.peek() {
+            self.position += 1;
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => {
+                    let raw =
+                        std::str::from_utf8(&self.input[start..self.position]).map_err(|_| ())?;
+                    return serde_json::from_str(raw)
+                        .map(Parsed::Complete)
+                        .map_err(|_| ());
+                }
+                _ => {}
+            }
+        }
+
+        // Cut off string: close it after the last complete character
+        let raw = String::from_utf8_lossy(&self.input[start + 1..]);
+        let mut raw = raw.trim_end_matches('\u{FFFD}').to_string();
+        loop {
+            if let Ok(value) = serde_json::from_str::<Value>(&format!("\"{raw}\"")) {
+                return Ok(Parsed::Partial(Some(value)));
+            }
+            // Drop the incomplete escape sequence at the end
+            match raw.rfind('\\') {
+                Some(index) => raw.truncate(index),
+                None => return Err(()),
+            }
+        }
+    }
+
+    fn literal(&mut self, literal: &str, value: Value) -> Result<Parsed, ()> {
+        let rest = &self.input[self.position..];
+        if rest.starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(Parsed::Complete(value))
+        } else if literal.as_bytes().starts_with(rest) {
+            self.position = self.input.len();
+            Ok(Parsed::Partial(None))
+        } else {
+            Err(())
+        }
+    }
+
+    fn number(&mut self) -> Result<Parsed, ()> {
+        let start = self.position;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.position += 1;
+        }
+        let raw = std::str::from_utf8(&self.input[start..self.position]).map_err(|_| ())?;
+
+        if self.position < self.input.len() {
+            return serde_json::from_str(raw)
+                .map(Parsed::Complete)
+                .map_err(|_| ());
+        }
+
+        // Number may still be growing, keep its complete prefix, if any
+        let raw = raw.trim_end_matches(|c: char| !c.is_ascii_digit());
+        Ok(Parsed::Partial(serde_json::from_str(raw).ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_partial;
+
+    #[test]
+    fn test_parse_partial() {
+        let document = r#"{"path": "a.txt", "lines": [1, -2.5, true], "content": "say \"hi\"\né", "done": false}"#;
+        assert_eq!(
+            parse_partial(document),
+            Some(serde_json::from_str(document).unwrap())
+        );
+
+        let cases = [
+            ("", None),
+            ("  {", Some(json!({}))),
+            (r#"{"pa"#, Some(json!({}))),
+            (r#"{"path""#, Some(json!({}))),
+            (r#"{"path": "#, Some(json!({}))),
+            (r#"{"path": "a.t"#, Some(json!({"path": "a.t"}))),
+            (
+                r#"{"path": "a.txt", "lines": [1, -"#,
+                Some(json!({"path": "a.txt", "lines": [1]})),
+            ),
+            (r#"{"lines": [1, -2."#, Some(json!({"lines": [1, -2]}))),
+            (r#"{"lines": [1, tr"#, Some(json!({"lines": [1]}))),
+            (
+                r#"{"content": "say \"hi\"\"#,
+                Some(json!({"content": "say \"hi\""})),
+            ),
+            (r#"{"content": "\u00"#, Some(json!({"content": ""}))),
+            (
+                r#"{"content": "é", "done": fal"#,
+                Some(json!({"content": "é"})),
+            ),
+            (
+                r#"{"nested": {"a": [{"b": "c"#,
+                Some(json!({"nested": {"a": [{"b": "c"}]}})),
+            ),
+            (r#"{"a": 1,"#, Some(json!({"a": 1}))),
+            (r#"{"a": 1 "b""#, None),
+            (r#"{"a": 1}}"#, None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_partial(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_parse_partial_depth_limit() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse_partial(&nested(128)).is_some());
+        assert_eq!(parse_partial(&nested(129)), None);
+
+        // Deep documents are rejected instead of overflowing the stack
+        assert_eq!(parse_partial(&"[".repeat(100_000)), None);
+        assert_eq!(parse_partial(&r#"{"a":"#.repeat(100_000)), None);
+    }
+}
@@ -0,0 +1,383 @@
+//! Client side [rate limits](https://platform.openai.com/docs/guides/rate-limits) enforced before requests are sent.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    types::{
+        ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPart,
+        ChatCompletionRequestUserMessageContent, CreateChatCompletionRequest,
+        CreateCompletionRequest, CreateEmbeddingRequest, EmbeddingInput, Prompt,
+    },
+    util::{TOKENS_PER_IMAGE, TOKENS_PER_MESSAGE},
+};
+
+/// Requests-per-minute and tokens-per-minute budget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Maximum number of requests in a minute, 0 for no limit on requests.
+    pub requests_per_minute: u32,
+    /// Maximum number of tokens (prompt and completion) in a minute, 0 for no limit on tokens.
+    pub tokens_per_minute: u32,
+}
+
+impl RateLimit {
+    /// Budget of `requests_per_minute` requests and `tokens_per_minute` tokens,
+    /// as listed for your organization in the [limits](https://platform.openai.com/account/limits) page.
+    ///
+    /// A value of 0 leaves that dimension unlimited, for example `RateLimit::new(0, 10_000)` only limits tokens.
+    pub fn new(requests_per_minute: u32, tokens_per_minute: u32) -> Self {
+        Self {
+            requests_per_minute,
+            tokens_per_minute,
+        }
+    }
+}
+
+/// Token bucket rate limiter which makes API calls wait for capacity instead of getting rate limited by the server.
+///
+/// Budgets are tracked per model: a model uses the limit set with [RateLimiter::with_model_limit],
+/// otherwise the one set with [RateLimiter::with_default_limit]. Models without any limit are not throttled.
+///
+/// Clones of a [RateLimiter], and of the [crate::Client] it is attached to, share the same budget.
+/// Capacity is reserved as soon as a request asks for it, so concurrent requests are sent in the order they waited.
+///
+/// ```
+/// use async_openai::{rate_limiter::{RateLimit, RateLimiter}, Client};
+///
+/// let rate_limiter = RateLimiter::new()
+///     .with_default_limit(RateLimit::new(500, 10_000))
+///     .with_model_limit("gpt-4", RateLimit::new(500, 10_000))
+///     .with_model_limit("text-embedding-ada-002", RateLimit::new(3_000, 1_000_000));
+///
+/// let client = Client::new().with_rate_limiter(rate_limiter);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    state: Arc<Mutex<RateLimiterState>>,
+}
+
+#[derive(Debug, Default)]
+struct RateLimiterState {
+    default_limit: Option<RateLimit>,
+    model_limits: HashMap<String, RateLimit>,
+    buckets: HashMap<String, (TokenBucket, TokenBucket)>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            capacity: per_minute as f64,
+            available: per_minute as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Apply a limit changed since the bucket was created, without adding capacity.
+    fn resize(&mut self, per_minute: u32) {
+        self.capacity = per_minute as f64;
+        self.available = self.available.min(self.capacity);
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.available = (self.available + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// Consume `amount`, possibly going into debt, and return the time until the debt is refilled.
+    /// A cost larger than the capacity only waits for a full bucket.
+    fn reserve(&mut self, amount: f64) -> Duration {
+        if self.capacity <= 0.0 {
+            return Duration::ZERO;
+        }
+        self.available -= amount.min(self.capacity);
+        if self.available >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.available * 60.0 / self.capacity)
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Rate limiter without any limit, use [RateLimiter::with_default_limit] or [RateLimiter::with_model_limit] to set them.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Limit applied to models which don't have a limit set with [RateLimiter::with_model_limit].
+    /// Each model still gets its own budget.
+    pub fn with_default_limit(self, limit: RateLimit) -> Self {
+        self.state.lock().unwrap().default_limit = Some(limit);
+        self
+    }
+
+    /// Limit applied to requests for `model`, also to the requests already waiting.
+    pub fn with_model_limit<S: Into<String>>(self, model: S, limit: RateLimit) -> Self {
+        self.state
+            .lock()
+            .unwrap()
+            .model_limits
+            .insert(model.into(), limit);
+        self
+    }
+
+    /// Reserve one request and `tokens` tokens in the budget of `model`, and wait until they are available.
+    pub async fn acquire(&self, model: &str, tokens: u32) {
+        let wait = self.reserve(model, tokens);
+        if !wait.is_zero() {
+            tracing::debug!("Waiting {wait:?} for rate limit capacity of {model}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Consume the capacity, ahead of its availability, and return the time to wait for it.
+    fn reserve(&self, model: &str, tokens: u32) -> Duration {
+        let mut state = self.state.lock().unwrap();
+
+        let limit = match state
+            .model_limits
+            .get(model)
+            .or(state.default_limit.as_ref())
+        {
+            Some(limit) => *limit,
+            None => return Duration::ZERO,
+        };
+
+        let (requests, tokens_bucket) =
+            state.buckets.entry(model.to_string()).or_insert_with(|| {
+                (
+                    TokenBucket::new(limit.requests_per_minute),
+                    TokenBucket::new(limit.tokens_per_minute),
+                )
+            });
+
+        let now = Instant::now();
+        requests.refill(now);
+        tokens_bucket.refill(now);
+        requests.resize(limit.requests_per_minute);
+        tokens_bucket.resize(limit.tokens_per_minute);
+
+        requests
+            .reserve(1.0)
+            .max(tokens_bucket.reserve(tokens as f64))
+    }
+}
+
+/// Requests which consume a [RateLimiter] budget.
+pub(crate) trait RateLimited {
+    /// Model whose budget is consumed.
+    fn model(&self) -> &str;
+    /// Estimate of prompt and completion tokens the server counts against the tokens-per-minute limit.
+    fn estimated_tokens(&self) -> u32;
+}
+
+/// Exact prompt tokens with the `tokenizer` feature, when the encoding of the model is known.
+#[cfg(feature = "tokenizer")]
+fn count_tokens(
+    model: &str,
+    count: impl FnOnce(crate::tokenizer::Tokenizer) -> usize,
+) -> Option<u32> {
+    crate::tokenizer::Tokenizer::for_model(model)
+        .ok()
+        .map(|tokenizer| count(tokenizer) as u32)
+}
+
+/// Rough estimate of ~4 characters per token for English text.
+fn estimate_text_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32 + 3) / 4
+}
+
+#[allow(deprecated)]
+fn estimate_message_tokens(message: &ChatCompletionRequestMessage) -> u32 {
+    let content = match message {
+        ChatCompletionRequestMessage::System(message) => estimate_text_tokens(&message.content),
+        ChatCompletionRequestMessage::User(message) => match &message.content {
+            ChatCompletionRequestUserMessageContent::Text(text) => estimate_text_tokens(text),
+            ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    ChatCompletionRequestMessageContentPart::Text(text) => {
+                        estimate_text_tokens(&text.text)
+                    }
+                    ChatCompletionRequestMessageContentPart::Image(_) => TOKENS_PER_IMAGE as u32,
+                })
+                .sum(),
+        },
+        ChatCompletionRequestMessage::Assistant(message) => {
+            message.content.as_deref().map_or(0, estimate_text_tokens)
+                + message.tool_calls.iter().flatten().fold(0, |sum, call| {
+                    sum + estimate_text_tokens(&call.function.name)
+                        + estimate_text_tokens(&call.function.arguments)
+                })
+                + message.function_call.as_ref().map_or(0, |call| {
+                    estimate_text_tokens(&call.name) + estimate_text_tokens(&call.arguments)
+                })
+        }
+        ChatCompletionRequestMessage::Tool(message) => estimate_text_tokens(&message.content),
+        ChatCompletionRequestMessage::Function(message) => {
+            message.content.as_deref().map_or(0, estimate_text_tokens)
+        }
+    };
+
+    content + TOKENS_PER_MESSAGE as u32
+}
+
+impl RateLimited for CreateChatCompletionRequest {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn estimated_tokens(&self) -> u32 {
+        let completion = self.max_tokens.unwrap_or(0) as u32 * self.n.unwrap_or(1).max(1) as u32;
+
+        #[cfg(feature = "tokenizer")]
+        if let Some(prompt) = count_tokens(&self.model, |tokenizer| tokenizer.count_request(self)) {
+            return prompt + completion;
+        }
+
+        let prompt: u32 = self.messages.iter().map(estimate_message_tokens).sum();
+        prompt + completion
+    }
+}
+
+impl RateLimited for CreateCompletionRequest {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn estimated_tokens(&self) -> u32 {
+        let completions = self.best_of.or(self.n).unwrap_or(1).max(1) as u32;
+        // Server defaults max_tokens to 16 for completions
+        let completion = self.max_tokens.unwrap_or(16) as u32 * completions;
+
+        #[cfg(feature = "tokenizer")]
+        if let Some(prompt) = count_tokens(&self.model, |tokenizer| {
+            tokenizer.count_prompt(&self.prompt)
+        }) {
+            return prompt + completion;
+        }
+
+        let prompt = match &self.prompt {
+            Prompt::String(text) => estimate_text_tokens(text),
+            Prompt::StringArray(texts) => texts.iter().map(|t| estimate_text_tokens(t)).sum(),
+            Prompt::IntegerArray(tokens) => tokens.len() as u32,
+            Prompt::ArrayOfIntegerArray(tokens) => tokens.iter().map(|t| t.len() as u32).sum(),
+        };
+        prompt + completion
+    }
+}
+
+impl RateLimited for CreateEmbeddingRequest {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn estimated_tokens(&self) -> u32 {
+        #[cfg(feature = "tokenizer")]
+        if let Some(tokens) = count_tokens(&self.model, |tokenizer| {
+            tokenizer.count_embedding_input(&self.input)
+        }) {
+            return tokens;
+        }
+
+        match &self.input {
+            EmbeddingInput::String(text) => estimate_text_tokens(text),
+            EmbeddingInput::StringArray(texts) => {
+                texts.iter().map(|t| estimate_text_tokens(t)).sum()
+            }
+            EmbeddingInput::IntegerArray(tokens) => tokens.len() as u32,
+            EmbeddingInput::ArrayOfIntegerArray(tokens) => {
+                tokens.iter().map(|t| t.len() as u32).sum()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{RateLimit, RateLimited, RateLimiter};
+    use crate::types::{
+        ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+        CreateChatCompletionRequestArgs,
+    };
+
+    #[test]
+    fn test_rate_limiter_budgets() {
+        let limiter = RateLimiter::new()
+            .with_default_limit(RateLimit::new(2, 1_000))
+            .with_model_limit("gpt-4", RateLimit::new(60, 100));
+        let clone = limiter.clone();
+
+        // Requests per minute of default limit, shared between clones
+        assert!(limiter.reserve("gpt-3.5-turbo", 10).is_zero());
+        assert!(clone.reserve("gpt-3.5-turbo", 10).is_zero());
+        let wait = limiter.reserve("gpt-3.5-turbo", 10);
+        assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30));
+
+        // Waiting requests hold their reservation, later ones wait behind them
+        let wait = limiter.reserve("gpt-3.5-turbo", 10);
+        assert!(wait > Duration::from_secs(59) && wait <= Duration::from_secs(60));
+
+        // Tokens per minute of model limit, budgets are separate per model
+        assert!(limiter.reserve("gpt-4", 80).is_zero());
+        let wait = limiter.reserve("gpt-4", 50);
+        assert!(wait > Duration::from_secs(17) && wait <= Duration::from_secs(18));
+
+        // Models without limits, or with a limit of 0, are not throttled
+        let unlimited = RateLimiter::new().with_model_limit("gpt-4", RateLimit::new(0, 0));
+        assert!(unlimited.reserve("gpt-3.5-turbo", u32::MAX).is_zero());
+        assert!(unlimited.reserve("gpt-4", u32::MAX).is_zero());
+    }
+
+    #[test]
+    fn test_rate_limiter_limit_change() {
+        let limiter = RateLimiter::new().with_model_limit("gpt-4", RateLimit::new(60, 1_000));
+        assert!(limiter.reserve("gpt-4", 950).is_zero());
+
+        // Existing budget follows the new limit
+        let limiter = limiter.with_model_limit("gpt-4", RateLimit::new(60, 100));
+        let wait = limiter.reserve("gpt-4", 100);
+        assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_chat_token_estimate() {
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-4")
+            .max_tokens(100_u16)
+            .n(2)
+            .messages([
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .content("You are a helpful assistant.")
+                    .build()
+                    .unwrap()
+                    .into(),
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content("Hello!")
+                    .build()
+                    .unwrap()
+                    .into(),
+            ])
+            .build()
+            .unwrap();
+
+        // (7 + 3) + (2 + 3) estimated prompt tokens, or 19 counted ones, and 2 * 100 completion tokens
+        let prompt = if cfg!(feature = "tokenizer") { 19 } else { 15 };
+        assert_eq!(request.estimated_tokens(), prompt + 200);
+    }
+}
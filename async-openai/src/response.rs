@@ -0,0 +1,146 @@
+//! Metadata of API responses, such as the request id and [rate limits](https://platform.openai.com/docs/guides/rate-limits/rate-limits-in-headers), parsed from response headers.
+use std::time::Duration;
+
+use reqwest::{header::HeaderMap, StatusCode};
+
+use crate::util::parse_duration;
+
+/// Rate limit state of the organization as reported by the `x-ratelimit-*` headers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitInfo {
+    /// The maximum number of requests that are permitted before exhausting the rate limit.
+    pub limit_requests: Option<u64>,
+    /// The maximum number of tokens that are permitted before exhausting the rate limit.
+    pub limit_tokens: Option<u64>,
+    /// The remaining number of requests that are permitted before exhausting the rate limit.
+    pub remaining_requests: Option<u64>,
+    /// The remaining number of tokens that are permitted before exhausting the rate limit.
+    pub remaining_tokens: Option<u64>,
+    /// The time until the rate limit (based on requests) resets to its initial state.
+    pub reset_requests: Option<Duration>,
+    /// The time until the rate limit (based on tokens) resets to its initial state.
+    pub reset_tokens: Option<Duration>,
+}
+
+/// Metadata of an API response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseMeta {
+    /// HTTP status code of the response.
+    pub status: StatusCode,
+    /// Unique identifier of the request from `x-request-id` header, useful when contacting support.
+    pub request_id: Option<String>,
+    /// Time taken by the server to process the request from `openai-processing-ms` header.
+    pub processing_time: Option<Duration>,
+    /// Organization which the request was attributed to from `openai-organization` header.
+    pub organization: Option<String>,
+    /// API version from `openai-version` header.
+    pub version: Option<String>,
+    /// Model which served the request from `openai-model` header.
+    pub model: Option<String>,
+    /// Rate limit state after this request.
+    pub rate_limit: RateLimitInfo,
+    /// All response headers, including the ones parsed above.
+    pub headers: HeaderMap,
+}
+
+impl ResponseMeta {
+    pub(crate) fn new(status: StatusCode, headers: HeaderMap) -> Self {
+        let string = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+        let number = |name: &str| string(name).and_then(|value| value.trim().parse::<u64>().ok());
+        let duration = |name: &str| string(name).and_then(|value| parse_duration(&value));
+
+        Self {
+            status,
+            request_id: string("x-request-id"),
+            processing_time: string("openai-processing-ms")
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|millis| !millis.is_nan())
+                .and_then(|millis| Duration::try_from_secs_f64(millis.max(0.0) / 1_000.0).ok()),
+            organization: string("openai-organization"),
+            version: string("openai-version"),
+            model: string("openai-model"),
+            rate_limit: RateLimitInfo {
+                limit_requests: number("x-ratelimit-limit-requests"),
+                limit_tokens: number("x-ratelimit-limit-tokens"),
+                remaining_requests: number("x-ratelimit-remaining-requests"),
+                remaining_tokens: number("x-ratelimit-remaining-tokens"),
+                reset_requests: duration("x-ratelimit-reset-requests"),
+                reset_tokens: duration("x-ratelimit-reset-tokens"),
+            },
+            headers,
+        }
+    }
+}
+
+/// Deserialized response body along with its [ResponseMeta].
+///
+/// Returned by the `*_with_meta` variants of API calls, for example [crate::Chat::create_with_meta].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response<T> {
+    /// The deserialized response body.
+    pub data: T,
+    /// Metadata parsed from the response headers.
+    pub meta: ResponseMeta,
+}
+
+impl<T> Response<T> {
+    /// Discard the metadata and return the response body.
+    pub fn into_data(self) -> T {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{header::HeaderMap, StatusCode};
+
+    use super::ResponseMeta;
+
+    #[test]
+    fn test_response_meta_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req_123".parse().unwrap());
+        headers.insert("openai-processing-ms", "412".parse().unwrap());
+        headers.insert("x-ratelimit-limit-requests", "3500".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-tokens", "89000".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "17ms".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "1m1.2s".parse().unwrap());
+
+        let meta = ResponseMeta::new(StatusCode::OK, headers);
+
+        assert_eq!(meta.request_id.as_deref(), Some("req_123"));
+        assert_eq!(meta.processing_time, Some(Duration::from_millis(412)));
+        assert_eq!(meta.rate_limit.limit_requests, Some(3500));
+        assert_eq!(meta.rate_limit.limit_tokens, None);
+        assert_eq!(meta.rate_limit.remaining_tokens, Some(89000));
+        assert_eq!(
+            meta.rate_limit.reset_requests,
+            Some(Duration::from_millis(17))
+        );
+        assert_eq!(
+            meta.rate_limit.reset_tokens,
+            Some(Duration::from_millis(61200))
+        );
+    }
+
+    #[test]
+    fn test_response_meta_out_of_range_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("openai-processing-ms", "1e30".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "inf".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "1e30s".parse().unwrap());
+
+        let meta = ResponseMeta::new(StatusCode::OK, headers);
+
+        assert_eq!(meta.processing_time, None);
+        assert_eq!(meta.rate_limit.reset_requests, None);
+        assert_eq!(meta.rate_limit.reset_tokens, None);
+    }
+}
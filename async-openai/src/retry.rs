@@ -0,0 +1,259 @@
+//! Policy deciding which failed API calls are retried with backoff.
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::StatusCode;
+
+use crate::error::ApiError;
+
+/// Condition matched against a failed attempt of an API call.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RetryCondition {
+    /// Response has this HTTP status code.
+    Status(u16),
+    /// Response has a HTTP status code within this inclusive range, for example `500..=599`.
+    StatusRange(u16, u16),
+    /// Error object in the response has this `type`, for example `server_error`.
+    ErrorType(String),
+    /// Error object in the response has this `code`, for example `rate_limit_exceeded`.
+    ErrorCode(String),
+    /// Connection to the server could not be established.
+    Connect,
+    /// Request or reading its response timed out.
+    Timeout,
+}
+
+/// A failed attempt as seen by [RetryPolicy].
+#[derive(Debug)]
+pub(crate) enum AttemptFailure<'a> {
+    /// Server responded with a non success status code.
+    Response {
+        status: StatusCode,
+        error: Option<&'a ApiError>,
+    },
+    /// Request could not be sent or its response could not be read.
+    Transport(&'a reqwest::Error),
+}
+
+impl RetryCondition {
+    fn matches(&self, failure: &AttemptFailure) -> bool {
+        match (self, failure) {
+            (Self::Status(code), AttemptFailure::Response { status, .. }) => {
+                status.as_u16() == *code
+            }
+            (Self::StatusRange(from, to), AttemptFailure::Response { status, .. }) => {
+                (*from..=*to).contains(&status.as_u16())
+            }
+            (
+                Self::ErrorType(r#type),
+                AttemptFailure::Response {
+                    error: Some(error), ..
+                },
+            ) => error.r#type.as_deref() == Some(r#type.as_str()),
+            (
+                Self::ErrorCode(code),
+                AttemptFailure::Response {
+                    error: Some(error), ..
+                },
+            ) => error.code.as_ref().and_then(|c| c.as_str()) == Some(code.as_str()),
+            (Self::Connect, AttemptFailure::Transport(e)) => e.is_connect(),
+            (Self::Timeout, AttemptFailure::Transport(e)) => e.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+/// Classifies failed attempts of API calls as retryable or fatal.
+///
+/// Rules are evaluated in the order they were added and the first matching rule decides;
+/// failures which match no rule are not retried. Retried attempts wait according to the
+/// [exponential backoff](crate::Client::with_backoff) of the client, or the delay requested by the server.
+///
+/// The default policy retries rate limits (429), except when the quota is exhausted (`insufficient_quota`),
+/// for up to 10 attempts. Server errors, connect errors and timeouts are retried when opted in
+/// with [RetryPolicy::retry_transient_errors].
+///
+/// ```
+/// use async_openai::{retry::{RetryCondition, RetryPolicy}, Client};
+///
+/// let policy = RetryPolicy::default()
+///     .never_retry_on(RetryCondition::Status(503))
+///     .retry_transient_errors()
+///     .with_max_attempts(5)
+///     .with_jitter(0.3);
+///
+/// let client = Client::new().with_retry_policy(policy);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    rules: Vec<(RetryCondition, bool)>,
+    max_attempts: Option<u32>,
+    jitter: Option<f64>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+            // API returns 429 also when:
+            // "You exceeded your current quota, please check your plan and billing details."
+            .never_retry_on(RetryCondition::ErrorType("insufficient_quota".into()))
+            .retry_on(RetryCondition::Status(429))
+            .with_max_attempts(10)
+    }
+}
+
+impl RetryPolicy {
+    /// Policy without any rule, which never retries.
+    pub fn none() -> Self {
+        Self {
+            rules: vec![],
+            max_attempts: None,
+            jitter: None,
+        }
+    }
+
+    /// Retry failures matching `condition`, unless an earlier rule matched.
+    pub fn retry_on(mut self, condition: RetryCondition) -> Self {
+        self.rules.push((condition, true));
+        self
+    }
+
+    /// Don't retry failures matching `condition`, unless an earlier rule matched.
+    pub fn never_retry_on(mut self, condition: RetryCondition) -> Self {
+        self.rules.push((condition, false));
+        self
+    }
+
+    /// Retry server errors (500, 502, 503, 504 and `server_error`), connect errors and timeouts,
+    /// unless an earlier rule matched.
+    ///
+    /// Requests which are not idempotent, such as creating a run, may be applied twice
+    /// when the server failed after processing them.
+    pub fn retry_transient_errors(self) -> Self {
+        self.retry_on(RetryCondition::Status(500))
+            .retry_on(RetryCondition::Status(502))
+            .retry_on(RetryCondition::Status(503))
+            .retry_on(RetryCondition::Status(504))
+            .retry_on(RetryCondition::ErrorType("server_error".into()))
+            .retry_on(RetryCondition::Connect)
+            .retry_on(RetryCondition::Timeout)
+    }
+
+    /// Maximum number of attempts, including the first one.
+    /// Without it retries are bounded by the `max_elapsed_time` of the backoff.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Randomize delays between attempts by up to `factor` (0.0 to 1.0) of their length,
+    /// so that concurrent clients don't retry in lockstep.
+    /// Overrides the `randomization_factor` of the backoff, and also applies to delays requested by the server.
+    pub fn with_jitter(mut self, factor: f64) -> Self {
+        self.jitter = Some(factor.clamp(0.0, 1.0));
+        self
+    }
+
+    pub(crate) fn is_retryable(&self, failure: &AttemptFailure) -> bool {
+        self.rules
+            .iter()
+            .find(|(condition, _)| condition.matches(failure))
+            .is_some_and(|(_, retry)| *retry)
+    }
+
+    /// Whether another attempt is allowed after `attempts` attempts were made.
+    pub(crate) fn allows_attempt(&self, attempts: u32) -> bool {
+        self.max_attempts.map_or(true, |max| attempts < max)
+    }
+
+    pub(crate) fn backoff(
+        &self,
+        backoff: &backoff::ExponentialBackoff,
+    ) -> backoff::ExponentialBackoff {
+        let mut backoff = backoff.clone();
+        if let Some(jitter) = self.jitter {
+            backoff.randomization_factor = jitter;
+        }
+        backoff
+    }
+
+    /// Add jitter to a delay requested by the server.
+    pub(crate) fn jittered(&self, delay: Duration) -> Duration {
+        match self.jitter {
+            Some(jitter) if jitter > 0.0 => {
+                delay.mul_f64(1.0 + rand::thread_rng().gen_range(0.0..jitter))
+            }
+            _ => delay,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::{AttemptFailure, RetryCondition, RetryPolicy};
+    use crate::error::ApiError;
+
+    fn api_error(r#type: &str) -> ApiError {
+        ApiError {
+            message: "".into(),
+            r#type: Some(r#type.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_retry_policy() {
+        let policy = RetryPolicy::default();
+        let failure = |status: u16, error: &ApiError| {
+            policy.is_retryable(&AttemptFailure::Response {
+                status: StatusCode::from_u16(status).unwrap(),
+                error: Some(error),
+            })
+        };
+
+        assert!(failure(429, &api_error("requests")));
+        assert!(!failure(429, &api_error("insufficient_quota")));
+        assert!(!failure(503, &api_error("server_error")));
+        assert!(!failure(400, &api_error("invalid_request_error")));
+        assert!(policy.allows_attempt(9));
+        assert!(!policy.allows_attempt(10));
+
+        let policy = RetryPolicy::default().retry_transient_errors();
+        let failure = |status: u16, error: &ApiError| {
+            policy.is_retryable(&AttemptFailure::Response {
+                status: StatusCode::from_u16(status).unwrap(),
+                error: Some(error),
+            })
+        };
+        assert!(!failure(429, &api_error("insufficient_quota")));
+        assert!(failure(503, &api_error("server_error")));
+        assert!(failure(520, &api_error("server_error")));
+        assert!(!failure(400, &api_error("invalid_request_error")));
+        assert!(policy.is_retryable(&AttemptFailure::Response {
+            status: StatusCode::BAD_GATEWAY,
+            error: None,
+        }));
+    }
+
+    #[test]
+    fn test_retry_policy_rules_order() {
+        let policy = RetryPolicy::none()
+            .never_retry_on(RetryCondition::Status(501))
+            .retry_on(RetryCondition::StatusRange(500, 599))
+            .with_max_attempts(3);
+        let failure = |status: u16| {
+            policy.is_retryable(&AttemptFailure::Response {
+                status: StatusCode::from_u16(status).unwrap(),
+                error: None,
+            })
+        };
+
+        assert!(!failure(501));
+        assert!(failure(502));
+        assert!(!failure(429));
+        assert!(policy.allows_attempt(2));
+        assert!(!policy.allows_attempt(3));
+    }
+}
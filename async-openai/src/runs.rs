@@ -1,16 +1,86 @@
+use std::time::{Duration, Instant};
+
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
     config::Config,
     error::OpenAIError,
+    pagination::paginate,
     steps::Steps,
+    tools::ToolRegistry,
     types::{
-        CreateRunRequest, ListRunsResponse, ModifyRunRequest, RunObject,
+        CreateRunRequest, ListRunsQuery, ListRunsResponse, ModifyRunRequest, RunObject, RunStatus,
         SubmitToolOutputsRunRequest,
     },
     Client,
 };
 
+/// How [Runs::poll_until_terminal] waits for a run.
+///
+/// The run is retrieved every `interval`, which grows by `multiplier` after each attempt up to `max_interval`.
+/// Delays shorter than 10ms are raised to it.
+#[derive(Debug, Clone)]
+pub struct PollOptions {
+    /// Delay before the second retrieval of the run, defaults to 500ms.
+    pub interval: Duration,
+    /// Upper bound of the delay between retrievals, defaults to 5s.
+    pub max_interval: Duration,
+    /// Growth factor of the delay between retrievals, defaults to 1.5.
+    pub multiplier: f64,
+    /// Give up polling after this long, defaults to 10 minutes, the expiry of runs. `None` polls until the run finishes.
+    pub timeout: Option<Duration>,
+    /// Request the cancellation of the run when polling times out, defaults to `true`.
+    pub cancel_on_timeout: bool,
+}
+
+/// Shortest delay between retrievals of a run, so that a zero interval doesn't busy-loop.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(500),
+            max_interval: Duration::from_secs(5),
+            multiplier: 1.5,
+            timeout: Some(Duration::from_secs(600)),
+            cancel_on_timeout: true,
+        }
+    }
+}
+
+impl PollOptions {
+    /// Set [PollOptions::interval].
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set [PollOptions::max_interval].
+    pub fn with_max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// Set [PollOptions::multiplier].
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set [PollOptions::timeout], `None` to poll until the run finishes.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set [PollOptions::cancel_on_timeout].
+    pub fn with_cancel_on_timeout(mut self, cancel_on_timeout: bool) -> Self {
+        self.cancel_on_timeout = cancel_on_timeout;
+        self
+    }
+}
+
 /// Represents an execution run on a thread.
 ///
 /// Related guide: [Assistants](https://platform.openai.com/docs/assistants/overview)
@@ -61,6 +131,8 @@ impl<'c, C: Config> Runs<'c, C> {
     }
 
     /// Returns a list of runs belonging to a thread.
+    ///
+    /// `query` is usually a [ListRunsQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list<Q>(&self, query: &Q) -> Result<ListRunsResponse, OpenAIError>
     where
         Q: Serialize + ?Sized,
@@ -70,6 +142,23 @@ impl<'c, C: Config> Runs<'c, C> {
             .await
     }
 
+    /// Same as [Runs::list], only taking a [ListRunsQuery] so that mistakes in the query don't compile.
+    pub async fn list_typed(&self, query: &ListRunsQuery) -> Result<ListRunsResponse, OpenAIError> {
+        self.list(query).await
+    }
+
+    /// Returns all runs of the thread, fetching pages of `limit` objects as the stream is polled.
+    pub fn list_all(
+        &self,
+        query: ListRunsQuery,
+    ) -> impl Stream<Item = Result<RunObject, OpenAIError>> + 'c {
+        paginate::<C, ListRunsResponse>(
+            self.client,
+            format!("/threads/{}/runs", self.thread_id),
+            query,
+        )
+    }
+
     /// When a run has the status: "requires_action" and required_action.type is submit_tool_outputs, this endpoint can be used to submit the outputs from the tool calls once they're all completed. All outputs must be submitted in a single request.
     pub async fn submit_tool_outputs(
         &self,
@@ -96,4 +185,387 @@ impl<'c, C: Config> Runs<'c, C> {
             )
             .await
     }
+
+    /// Retrieve the run until it stops being `queued`, `in_progress` or `cancelling`, and return it.
+    ///
+    /// Returns as soon as the run `requires_action`, so that tool outputs can be submitted with
+    /// [Runs::submit_tool_outputs] before polling again. When `options.timeout` elapses first,
+    /// the run is cancelled if `options.cancel_on_timeout` is set and [OpenAIError::RunPollTimeout] is returned,
+    /// also when the cancellation fails.
+    pub async fn poll_until_terminal(
+        &self,
+        run_id: &str,
+        options: PollOptions,
+    ) -> Result<RunObject, OpenAIError> {
+        self.poll(run_id, &options, Instant::now()).await
+    }
+
+    /// [Runs::poll_until_terminal] with `options.timeout` counted from `started`.
+    async fn poll(
+        &self,
+        run_id: &str,
+        options: &PollOptions,
+        started: Instant,
+    ) -> Result<RunObject, OpenAIError> {
+        let mut interval = options.interval.max(MIN_POLL_INTERVAL);
+
+        loop {
+            let run = self.retrieve(run_id).await?;
+            match run.status {
+                RunStatus::Queued | RunStatus::InProgress | RunStatus::Cancelling => {}
+                _ => return Ok(run),
+            }
+
+            let delay = match options.timeout {
+                Some(timeout) => {
+                    let remaining = timeout.saturating_sub(started.elapsed());
+                    if remaining.is_zero() {
+                        return Err(self.timed_out(run_id, run, options).await);
+                    }
+                    interval.min(remaining)
+                }
+                None => interval,
+            };
+
+            tracing::debug!(
+                "Run {run_id} is {:?}, polling again in {delay:?}",
+                run.status
+            );
+            tokio::time::sleep(delay).await;
+            // Growth which overflows a duration, like an infinite multiplier, goes straight to the maximum
+            interval =
+                Duration::try_from_secs_f64(interval.as_secs_f64() * options.multiplier.max(1.0))
+                    .unwrap_or(options.max_interval)
+                    .min(options.max_interval)
+                    .max(MIN_POLL_INTERVAL);
+        }
+    }
+
+    /// Cancel the run if `options.cancel_on_timeout` is set, and return the timeout error with the latest run.
+    async fn timed_out(&self, run_id: &str, run: RunObject, options: &PollOptions) -> OpenAIError {
+        let run = if options.cancel_on_timeout && run.status != RunStatus::Cancelling {
+            self.cancel(run_id).await.unwrap_or_else(|e| {
+                tracing::warn!("Failed to cancel run {run_id} after polling timed out: {e}");
+                run
+            })
+        } else {
+            run
+        };
+        OpenAIError::RunPollTimeout(Box::new(run))
+    }
+
+    /// Poll the run like [Runs::poll_until_terminal], and each time it `requires_action`, run the tool calls
+    /// with the handlers of `tools` concurrently and submit their outputs, until the run completes, fails,
+    /// is cancelled or expires.
+    ///
+    /// Handler errors and panics are submitted to the model as `{"error": "..."}` outputs, see [ToolRegistry].
+    /// `options.timeout` applies to the whole call, including the time spent running tools.
+    pub async fn poll_with_tools(
+        &self,
+        run_id: &str,
+        tools: &ToolRegistry,
+        options: PollOptions,
+    ) -> Result<RunObject, OpenAIError> {
+        let started = Instant::now();
+
+        loop {
+            let run = self.poll(run_id, &options, started).await?;
+
+            let tool_calls = match (&run.status, &run.required_action) {
+                (RunStatus::RequiresAction, Some(action)) => &action.submit_tool_outputs.tool_calls,
+                _ => return Ok(run),
+            };
+            if options
+                .timeout
+                .is_some_and(|timeout| started.elapsed() >= timeout)
+            {
+                return Err(self.timed_out(run_id, run, &options).await);
+            }
+
+            let tool_outputs = tools.call_run_tools(tool_calls).await;
+            self.submit_tool_outputs(run_id, SubmitToolOutputsRunRequest { tool_outputs })
+                .await?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::PollOptions;
+    use crate::{
+        error::OpenAIError,
+        testing::{MockResponse, MockServer},
+        tools::ToolRegistry,
+        types::{FunctionObjectArgs, RunStatus, SubmitToolOutputsRunRequest},
+    };
+
+    fn run(status: &str) -> MockResponse {
+        run_with_action(status, serde_json::Value::Null)
+    }
+
+    fn run_with_action(status: &str, required_action: serde_json::Value) -> MockResponse {
+        MockResponse::json(&json!({
+            "id": "run_1",
+            "object": "thread.run",
+            "created_at": 0,
+            "thread_id": "thread_1",
+            "assistant_id": "asst_1",
+            "status": status,
+            "required_action": required_action,
+            "last_error": null,
+            "expires_at": null,
+            "started_at": null,
+            "cancelled_at": null,
+            "failed_at": null,
+            "completed_at": null,
+            "model": "gpt-4",
+            "instructions": "",
+            "tools": [],
+            "file_ids": [],
+            "metadata": null
+        }))
+    }
+
+    fn options() -> PollOptions {
+        PollOptions::default()
+            .with_interval(Duration::from_millis(1))
+            .with_max_interval(Duration::from_millis(5))
+    }
+
+    #[tokio::test]
+    async fn test_poll_until_terminal() {
+        let server = MockServer::start().await;
+        server
+            .mock("GET", "/threads/*/runs/*", run("queued"))
+            .mock("GET", "/threads/*/runs/*", run("in_progress"))
+            .mock("GET", "/threads/*/runs/*", run("requires_action"));
+        let client = server.client();
+
+        let run = client
+            .threads()
+            .runs("thread_1")
+            .poll_until_terminal("run_1", options())
+            .await
+            .unwrap();
+        assert_eq!(run.status, RunStatus::RequiresAction);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_poll_timeout_cancels_run() {
+        let server = MockServer::start().await;
+        server
+            .mock("GET", "/threads/*/runs/*", run("in_progress"))
+            .mock("POST", "/threads/*/runs/*/cancel", run("cancelling"));
+        let client = server.client();
+
+        let result = client
+            .threads()
+            .runs("thread_1")
+            .poll_until_terminal(
+                "run_1",
+                options().with_timeout(Some(Duration::from_millis(20))),
+            )
+            .await;
+        let Err(OpenAIError::RunPollTimeout(run)) = result else {
+            panic!("expected a timeout");
+        };
+        assert_eq!(run.status, RunStatus::Cancelling);
+
+        let cancel = server.requests().pop().unwrap();
+        assert_eq!(cancel.path, "/threads/thread_1/runs/run_1/cancel");
+    }
+
+    #[tokio::test]
+    async fn test_poll_timeout_cancel_failure() {
+        let server = MockServer::start().await;
+        server
+            .mock("GET", "/threads/*/runs/*", run("in_progress"))
+            .mock(
+                "POST",
+                "/threads/*/runs/*/cancel",
+                MockResponse::error(400, "invalid_request_error", None, "Cannot cancel run"),
+            );
+        let client = server.client();
+
+        // Timeout is still returned, with the run as last retrieved
+        let result = client
+            .threads()
+            .runs("thread_1")
+            .poll_until_terminal(
+                "run_1",
+                options().with_timeout(Some(Duration::from_millis(20))),
+            )
+            .await;
+        let Err(OpenAIError::RunPollTimeout(run)) = result else {
+            panic!("expected a timeout");
+        };
+        assert_eq!(run.status, RunStatus::InProgress);
+    }
+
+    #[tokio::test]
+    async fn test_poll_extreme_intervals() {
+        let server = MockServer::start().await;
+        server.mock("GET", "/threads/*/runs/*", run("in_progress"));
+        let client = server.client();
+
+        // Zero interval doesn't busy-loop, an infinite multiplier doesn't panic
+        let options = PollOptions::default()
+            .with_interval(Duration::ZERO)
+            .with_max_interval(Duration::from_millis(20))
+            .with_multiplier(f64::INFINITY)
+            .with_timeout(Some(Duration::from_millis(100)))
+            .with_cancel_on_timeout(false);
+        let result = client
+            .threads()
+            .runs("thread_1")
+            .poll_until_terminal("run_1", options)
+            .await;
+        assert!(matches!(result, Err(OpenAIError::RunPollTimeout(_))));
+        assert!(server.requests().len() <= 11);
+    }
+
+    #[tokio::test]
+    async fn test_poll_with_tools() {
+        let tool_call = |id: &str, arguments: &str| {
+            json!({
+                "id": id,
+                "type": "function",
+                "function": {"name": "divide", "arguments": arguments}
+            })
+        };
+        let server = MockServer::start().await;
+        server
+            .mock(
+                "GET",
+                "/threads/*/runs/*",
+                run_with_action(
+                    "requires_action",
+                    json!({
+                        "type": "submit_tool_outputs",
+                        "submit_tool_outputs": {
+                            "tool_calls": [
+                                tool_call("call_1", r#"{"a": 6, "b": 3}"#),
+                                tool_call("call_2", r#"{"a": 1, "b": 0}"#),
+                                tool_call("call_3", r#"{"a": -1, "b": 1}"#),
+                            ]
+                        }
+                    }),
+                ),
+            )
+            .mock("GET", "/threads/*/runs/*", run("completed"))
+            .mock(
+                "POST",
+                "/threads/*/runs/*/submit_tool_outputs",
+                run("queued"),
+            );
+
+        #[derive(serde::Deserialize)]
+        struct DivideArgs {
+            a: i64,
+            b: i64,
+        }
+        let tools = ToolRegistry::new().with_tool(
+            FunctionObjectArgs::default()
+                .name("divide")
+                .build()
+                .unwrap(),
+            |args: DivideArgs| async move {
+                if args.a < 0 {
+                    return Err("negative numbers are not supported");
+                }
+                Ok(args.a / args.b)
+            },
+        );
+
+        let run = server
+            .client()
+            .threads()
+            .runs("thread_1")
+            .poll_with_tools("run_1", &tools, options())
+            .await
+            .unwrap();
+        assert_eq!(run.status, RunStatus::Completed);
+
+        let submitted: SubmitToolOutputsRunRequest = server.requests()[1].json().unwrap();
+        let outputs: Vec<_> = submitted
+            .tool_outputs
+            .iter()
+            .map(|output| output.output.as_deref().unwrap())
+            .collect();
+        assert_eq!(outputs[0], "2");
+        assert!(outputs[1].contains("tool panicked: attempt to divide by zero"));
+        assert_eq!(
+            outputs[2],
+            r#"{"error":"negative numbers are not supported"}"#
+        );
+        assert_eq!(
+            submitted.tool_outputs[1].tool_call_id.as_deref(),
+            Some("call_2")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_poll_with_tools_timeout() {
+        let server = MockServer::start().await;
+        server
+            .mock(
+                "GET",
+                "/threads/*/runs/*",
+                run_with_action(
+                    "requires_action",
+                    json!({
+                        "type": "submit_tool_outputs",
+                        "submit_tool_outputs": {
+                            "tool_calls": [{
+                                "id": "call_1",
+                                "type": "function",
+                                "function": {"name": "wait", "arguments": "{}"}
+                            }]
+                        }
+                    }),
+                ),
+            )
+            .mock(
+                "POST",
+                "/threads/*/runs/*/submit_tool_outputs",
+                run("queued"),
+            )
+            .mock("POST", "/threads/*/runs/*/cancel", run("cancelling"));
+
+        let tools = ToolRegistry::new().with_tool(
+            FunctionObjectArgs::default().name("wait").build().unwrap(),
+            |_: serde_json::Value| async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok::<_, String>("done")
+            },
+        );
+
+        // Run requiring action forever stops at the timeout of the whole call
+        let result = server
+            .client()
+            .threads()
+            .runs("thread_1")
+            .poll_with_tools(
+                "run_1",
+                &tools,
+                options().with_timeout(Some(Duration::from_millis(100))),
+            )
+            .await;
+        let Err(OpenAIError::RunPollTimeout(run)) = result else {
+            panic!("expected a timeout");
+        };
+        assert_eq!(run.status, RunStatus::Cancelling);
+
+        let submits = server
+            .requests()
+            .iter()
+            .filter(|request| request.path.ends_with("/submit_tool_outputs"))
+            .count();
+        assert!((1..=5).contains(&submits), "{submits} submits");
+    }
 }
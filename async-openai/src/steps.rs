@@ -1,9 +1,11 @@
+use futures::Stream;
 use serde::Serialize;
 
 use crate::{
     config::Config,
     error::OpenAIError,
-    types::{ListRunStepsResponse, RunStepObject},
+    pagination::paginate,
+    types::{ListRunStepsQuery, ListRunStepsResponse, RunStepObject},
     Client,
 };
 
@@ -34,6 +36,8 @@ impl<'c, C: Config> Steps<'c, C> {
     }
 
     /// Returns a list of run steps belonging to a run.
+    ///
+    /// `query` is usually a [ListRunStepsQuery], see [ListQuery](crate::types::ListQuery) for other values.
     pub async fn list<Q>(&self, query: &Q) -> Result<ListRunStepsResponse, OpenAIError>
     where
         Q: Serialize + ?Sized,
@@ -45,4 +49,24 @@ impl<'c, C: Config> Steps<'c, C> {
             )
             .await
     }
+
+    /// Same as [Steps::list], only taking a [ListRunStepsQuery] so that mistakes in the query don't compile.
+    pub async fn list_typed(
+        &self,
+        query: &ListRunStepsQuery,
+    ) -> Result<ListRunStepsResponse, OpenAIError> {
+        self.list(query).await
+    }
+
+    /// Returns all steps of the run, fetching pages of `limit` objects as the stream is polled.
+    pub fn list_all(
+        &self,
+        query: ListRunStepsQuery,
+    ) -> impl Stream<Item = Result<RunStepObject, OpenAIError>> + 'c {
+        paginate::<C, ListRunStepsResponse>(
+            self.client,
+            format!("/threads/{}/runs/{}/steps", self.thread_id, self.run_id),
+            query,
+        )
+    }
 }
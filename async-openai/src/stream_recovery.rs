@@ -0,0 +1,291 @@
+//! Recovery of chat completion streams which lose their connection before the model finished, see [StreamRecovery].
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use futures::StreamExt;
+
+use crate::{
+    error::{OpenAIError, StreamInterrupted},
+    types::{
+        ChatCompletionResponseStream, CreateChatCompletionRequest,
+        CreateChatCompletionStreamResponse,
+    },
+};
+
+/// How [crate::Chat::create_stream] handles a connection lost before the model finished generating,
+/// set with [crate::Chat::with_stream_recovery].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StreamRecovery {
+    /// Transport errors are items of the stream like any other error.
+    #[default]
+    Disabled,
+    /// End the stream with [OpenAIError::StreamInterrupted] carrying the content received so far.
+    Interrupt,
+    /// Re-issue the request up to `max_reissues` times when the connection is lost before the model generated
+    /// any content, then behave like [StreamRecovery::Interrupt].
+    ///
+    /// Once content was streamed the answer cannot be resumed, the stream is interrupted instead.
+    /// Only requests for a single choice are re-issued.
+    Reissue { max_reissues: u32 },
+}
+
+/// Sends a streaming request again, to continue an interrupted stream.
+pub(crate) type Reissue = Arc<
+    dyn Fn(
+            CreateChatCompletionRequest,
+        ) -> Pin<
+            Box<dyn Future<Output = Result<ChatCompletionResponseStream, OpenAIError>> + Send>,
+        > + Send
+        + Sync,
+>;
+
+/// Tracks progress of a chat completion stream to recover it according to [StreamRecovery].
+pub(crate) struct RecoverableStream {
+    request: CreateChatCompletionRequest,
+    stream: Option<ChatCompletionResponseStream>,
+    stream_recovery: StreamRecovery,
+    reissue: Reissue,
+    reissues: u32,
+    content: Vec<String>,
+    finished: Vec<bool>,
+    tool_calls: bool,
+}
+
+impl RecoverableStream {
+    pub(crate) fn new(
+        request: CreateChatCompletionRequest,
+        stream: ChatCompletionResponseStream,
+        stream_recovery: StreamRecovery,
+        reissue: Reissue,
+    ) -> Self {
+        Self {
+            request,
+            stream: Some(stream),
+            stream_recovery,
+            reissue,
+            reissues: 0,
+            content: vec![],
+            finished: vec![],
+            tool_calls: false,
+        }
+    }
+
+    pub(crate) fn boxed(self) -> ChatCompletionResponseStream {
+        Box::pin(futures::stream::unfold(self, |mut state| async move {
+            let item = state.next().await?;
+            Some((item, state))
+        }))
+    }
+
+    #[allow(deprecated)]
+    fn record(&mut self, chunk: &CreateChatCompletionStreamResponse) {
+        for choice in chunk.choices.iter() {
+            let index = choice.index as usize;
+            if self.content.len() <= index {
+                self.content.resize(index + 1, String::new());
+                self.finished.resize(index + 1, false);
+            }
+            if let Some(content) = &choice.delta.content {
+                self.content[index].push_str(content);
+            }
+            if choice.delta.tool_calls.is_some() || choice.delta.function_call.is_some() {
+                self.tool_calls = true;
+            }
+            if choice.finish_reason.is_some() {
+                self.finished[index] = true;
+            }
+        }
+    }
+
+    /// Whether every choice received its `finish_reason`.
+    fn is_finished(&self) -> bool {
+        !self.finished.is_empty() && self.finished.iter().all(|finished| *finished)
+    }
+
+    /// Whether the request can be sent again, which is only the case while nothing was generated:
+    /// a new answer cannot be joined to the content already streamed.
+    fn can_reissue(&self) -> bool {
+        match self.stream_recovery {
+            StreamRecovery::Reissue { max_reissues } => {
+                self.reissues < max_reissues
+                    && self.request.n.unwrap_or(1) <= 1
+                    && self.content.iter().all(|content| content.is_empty())
+                    && !self.tool_calls
+            }
+            _ => false,
+        }
+    }
+
+    async fn next(&mut self) -> Option<Result<CreateChatCompletionStreamResponse, OpenAIError>> {
+        let mut stream = self.stream.take()?;
+
+        loop {
+            let reason = match stream.next().await {
+                Some(Ok(chunk)) => {
+                    self.record(&chunk);
+                    self.stream = Some(stream);
+                    return Some(Ok(chunk));
+                }
+                Some(Err(OpenAIError::Reqwest(e))) if !self.is_finished() => e.to_string(),
+                // Errors sent by the server end the stream, which must not be reported again as interrupted
+                Some(Err(e)) => return Some(Err(e)),
+                None if !self.is_finished() => {
+                    "connection closed before the model finished".to_string()
+                }
+                None => return None,
+            };
+
+            let reason = if self.can_reissue() {
+                self.reissues += 1;
+                tracing::warn!("Re-issuing interrupted chat completion stream: {reason}");
+                match (self.reissue)(self.request.clone()).await {
+                    Ok(reissued) => {
+                        stream = reissued;
+                        continue;
+                    }
+                    Err(e) => e.to_string(),
+                }
+            } else {
+                reason
+            };
+
+            return Some(Err(OpenAIError::StreamInterrupted(StreamInterrupted {
+                reason,
+                partial_content: std::mem::take(&mut self.content),
+            })));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use futures::StreamExt;
+
+    use super::{RecoverableStream, Reissue, StreamRecovery};
+    use crate::{
+        error::{ApiError, OpenAIError},
+        types::{
+            ChatCompletionResponseStream, CreateChatCompletionRequestArgs,
+            CreateChatCompletionStreamResponse,
+        },
+    };
+
+    fn chunk(content: &str, finish_reason: Option<&str>) -> CreateChatCompletionStreamResponse {
+        serde_json::from_value(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion.chunk",
+            "created": 1694268190,
+            "model": "gpt-3.5-turbo",
+            "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": finish_reason}]
+        }))
+        .unwrap()
+    }
+
+    fn stream(items: Vec<CreateChatCompletionStreamResponse>) -> ChatCompletionResponseStream {
+        futures::stream::iter(items.into_iter().map(Ok)).boxed()
+    }
+
+    /// Text received from the stream as a user would assemble it, and the interrupted content if any.
+    async fn text(stream: ChatCompletionResponseStream) -> (String, Option<Vec<String>>) {
+        let mut text = String::new();
+        let mut stream = stream;
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(chunk) => text.extend(chunk.choices[0].delta.content.clone()),
+                Err(OpenAIError::StreamInterrupted(interrupted)) => {
+                    return (text, Some(interrupted.partial_content))
+                }
+                Err(e) => panic!("unexpected error {e}"),
+            }
+        }
+        (text, None)
+    }
+
+    #[tokio::test]
+    async fn test_recoverable_stream() {
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .messages([])
+            .build()
+            .unwrap();
+        let reissues = Arc::new(AtomicU32::new(0));
+        let reissue: Reissue = {
+            let reissues = reissues.clone();
+            Arc::new(move |_| {
+                reissues.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move {
+                    Ok(stream(vec![
+                        chunk("Hello", None),
+                        chunk(" world", Some("stop")),
+                    ]))
+                })
+            })
+        };
+
+        let interrupted = RecoverableStream::new(
+            request.clone(),
+            stream(vec![chunk("Hello", None)]),
+            StreamRecovery::Interrupt,
+            reissue.clone(),
+        );
+        assert_eq!(
+            text(interrupted.boxed()).await,
+            ("Hello".into(), Some(vec!["Hello".into()]))
+        );
+
+        // Content was already streamed, a new answer would be appended to it
+        let interrupted = RecoverableStream::new(
+            request.clone(),
+            stream(vec![chunk("Hello", None)]),
+            StreamRecovery::Reissue { max_reissues: 1 },
+            reissue.clone(),
+        );
+        assert_eq!(
+            text(interrupted.boxed()).await,
+            ("Hello".into(), Some(vec!["Hello".into()]))
+        );
+        assert_eq!(reissues.load(Ordering::SeqCst), 0);
+
+        // Nothing was generated yet, the answer is only the one of the re-issued request
+        let reissued = RecoverableStream::new(
+            request,
+            stream(vec![chunk("", None)]),
+            StreamRecovery::Reissue { max_reissues: 1 },
+            reissue,
+        );
+        assert_eq!(text(reissued.boxed()).await, ("Hello world".into(), None));
+        assert_eq!(reissues.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_recoverable_stream_error_event() {
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .messages([])
+            .build()
+            .unwrap();
+        let reissue: Reissue = Arc::new(|_| Box::pin(async { Ok(stream(vec![])) }));
+        let items = futures::stream::iter(vec![
+            Ok(chunk("Hello", None)),
+            Err(OpenAIError::ApiError(ApiError {
+                message: "The server had an error while processing your request.".into(),
+                ..Default::default()
+            })),
+        ])
+        .boxed();
+
+        // The error event ends the stream without a second, interrupted, error
+        let mut recovered =
+            RecoverableStream::new(request, items, StreamRecovery::Interrupt, reissue).boxed();
+        assert!(recovered.next().await.unwrap().is_ok());
+        assert!(matches!(
+            recovered.next().await,
+            Some(Err(OpenAIError::ApiError(_)))
+        ));
+        assert!(recovered.next().await.is_none());
+    }
+}
@@ -0,0 +1,869 @@
+//! In-process mock of the OpenAI API and record/replay of API calls, for testing code which uses the [Client] without network access.
+//!
+//! A [MockServer] listens on a local port and is used as the `api_base` of a [Client]. It answers requests in one of three modes:
+//!
+//! - Scripted: responses added with [MockServer::mock] are returned for matching requests, see [MockResponse] for JSON, SSE and error responses.
+//! - Record: requests are forwarded to the real API and the request/response pairs are saved to a fixture file, see [MockServer::record].
+//! - Replay: responses are served from a fixture file in the order they were recorded, see [MockServer::replay].
+//!
+//! All modes keep the requests made by the client for inspection, see [RecordedRequest].
+//!
+//! ```
+//! use async_openai::{
+//!     testing::{MockResponse, MockServer},
+//!     types::CreateEmbeddingRequestArgs,
+//! };
+//! use serde_json::json;
+//!
+//! # tokio_test::block_on(async {
+//! let server = MockServer::start().await;
+//! server.mock(
+//!     "POST",
+//!     "/embeddings",
+//!     MockResponse::json(&json!({
+//!         "object": "list",
+//!         "model": "text-embedding-ada-002",
+//!         "data": [{"index": 0, "object": "embedding", "embedding": [0.1, 0.2]}],
+//!         "usage": {"prompt_tokens": 2, "total_tokens": 2}
+//!     })),
+//! );
+//!
+//! let request = CreateEmbeddingRequestArgs::default()
+//!     .model("text-embedding-ada-002")
+//!     .input("Hello world")
+//!     .build()
+//!     .unwrap();
+//! let response = server.client().embeddings().create(request).await.unwrap();
+//!
+//! assert_eq!(response.data[0].embedding, vec![0.1, 0.2]);
+//! assert_eq!(server.requests()[0].json::<serde_json::Value>().unwrap()["input"], "Hello world");
+//! # });
+//! ```
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose, Engine};
+use bytes::Bytes;
+use futures::channel::oneshot;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
+    Method, StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    config::{Config, OpenAIConfig},
+    error::{map_deserialization_error, OpenAIError},
+    Client,
+};
+
+/// Response returned by a [MockServer] for a matching request.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    /// Parts of the body sent after `body`, each after waiting its delay.
+    chunks: Vec<(Duration, Bytes)>,
+    delay: Option<Duration>,
+}
+
+impl MockResponse {
+    /// Response with a JSON body, such as a chat completion or a list of files.
+    pub fn json<T: Serialize>(body: &T) -> Self {
+        Self::bytes(serde_json::to_vec(body).expect("failed to serialize mock response"))
+            .with_header(CONTENT_TYPE.as_str(), "application/json")
+    }
+
+    /// Response with a raw body, such as the audio of text to speech.
+    pub fn bytes<B: Into<Bytes>>(body: B) -> Self {
+        Self {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: body.into(),
+            chunks: vec![],
+            delay: None,
+        }
+        .with_header(CONTENT_TYPE.as_str(), "application/octet-stream")
+    }
+
+    /// Response with a raw body sent in `chunks`, waiting `interval` before each chunk after the first,
+    /// to test data split across reads and streams which are slow or never end.
+    pub fn chunked<B, I>(chunks: I, interval: Duration) -> Self
+    where
+        B: Into<Bytes>,
+        I: IntoIterator<Item = B>,
+    {
+        let mut chunks = chunks.into_iter().map(Into::into);
+        let mut response = Self::bytes(chunks.next().unwrap_or_default());
+        response.chunks = chunks.map(|chunk| (interval, chunk)).collect();
+        response
+    }
+
+    /// Server-sent events with each of `events` as JSON data, followed by `[DONE]`, as streamed by `stream: true` requests.
+    pub fn sse<T, I>(events: I) -> Self
+    where
+        T: Serialize,
+        I: IntoIterator<Item = T>,
+    {
+        let mut body = String::new();
+        for event in events {
+            let data = serde_json::to_string(&event).expect("failed to serialize mock event");
+            body.push_str(&format!("data: {data}\n\n"));
+        }
+        body.push_str("data: [DONE]\n\n");
+
+        Self::bytes(body).with_header(CONTENT_TYPE.as_str(), "text/event-stream")
+    }
+
+    /// Error object of the API with `status` code, decoded by the client into [OpenAIError::ApiError].
+    pub fn error(status: u16, r#type: &str, code: Option<&str>, message: &str) -> Self {
+        Self::json(&serde_json::json!({
+            "error": {
+                "message": message,
+                "type": r#type,
+                "param": null,
+                "code": code,
+            }
+        }))
+        .with_status(status)
+    }
+
+    /// Respond with `status` code instead of 200.
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = StatusCode::from_u16(status).expect("invalid status code");
+        self
+    }
+
+    /// Set a response header, such as `retry-after` or `x-request-id`.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()).expect("invalid header name"),
+            HeaderValue::from_str(value).expect("invalid header value"),
+        );
+        self
+    }
+
+    /// Wait before responding, to test timeouts and cancellation.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// Request received by a [MockServer].
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    /// Path relative to the `api_base` of the client, such as `/chat/completions`.
+    pub path: String,
+    /// Query string without the leading `?`.
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    /// Value of the header `name`, if it is valid UTF-8.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|value| value.to_str().ok())
+    }
+
+    /// Body deserialized from JSON, such as the request object of a `post` API call.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, OpenAIError> {
+        serde_json::from_slice(&self.body)
+            .map_err(|e| map_deserialization_error(e, self.body.as_ref()))
+    }
+
+    /// Fields of a `multipart/form-data` body, as sent by file uploads, audio transcriptions and image edits.
+    pub fn multipart(&self) -> Result<Vec<MultipartField>, OpenAIError> {
+        let content_type = self.header(CONTENT_TYPE.as_str()).unwrap_or_default();
+        parse_multipart(content_type, &self.body)
+    }
+}
+
+/// Field of a `multipart/form-data` request body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartField {
+    pub name: String,
+    /// Name of the uploaded file, for file fields.
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Bytes,
+}
+
+impl MultipartField {
+    /// Data of the field as text, with invalid UTF-8 replaced.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+}
+
+/// Request/response pair saved to fixture files by [MockServer::record].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    query: Option<String>,
+    /// JSON body of the request, multipart bodies are not saved.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    request: Option<serde_json::Value>,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    content_type: Option<String>,
+    /// Response body, when it is valid UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    body: Option<String>,
+    /// Base64 encoded response body, when it isn't valid UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    body_base64: Option<String>,
+}
+
+impl Exchange {
+    fn response(&self) -> MockResponse {
+        let body = match (&self.body, &self.body_base64) {
+            (Some(body), _) => Bytes::from(body.clone()),
+            (None, Some(body)) => general_purpose::STANDARD
+                .decode(body)
+                .map(Bytes::from)
+                .unwrap_or_default(),
+            (None, None) => Bytes::new(),
+        };
+
+        let mut response = MockResponse::bytes(body).with_status(self.status);
+        response.headers.remove(CONTENT_TYPE);
+        if let Some(content_type) = &self.content_type {
+            response = response.with_header(CONTENT_TYPE.as_str(), content_type);
+        }
+        response
+    }
+}
+
+/// The real API, which recorded requests are forwarded to.
+#[derive(Clone)]
+struct Upstream {
+    http_client: reqwest::Client,
+    url: Arc<dyn Fn(&str) -> String + Send + Sync>,
+    headers: HeaderMap,
+    query: Vec<(String, String)>,
+}
+
+enum Mode {
+    Scripted(Vec<Mock>),
+    Record {
+        upstream: Upstream,
+        fixture: PathBuf,
+        exchanges: Vec<Exchange>,
+    },
+    Replay(VecDeque<Exchange>),
+}
+
+struct Mock {
+    method: String,
+    path: String,
+    responses: VecDeque<MockResponse>,
+}
+
+impl Mock {
+    /// Segments of `path` equal to `*` match any segment, such as the id of an object.
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        let pattern = self.path.trim_matches('/').split('/');
+        let path = path.trim_matches('/').split('/');
+
+        self.method.eq_ignore_ascii_case(method.as_str())
+            && pattern.clone().count() == path.clone().count()
+            && pattern
+                .zip(path)
+                .all(|(pattern, segment)| pattern == "*" || pattern == segment)
+    }
+}
+
+struct State {
+    mode: Mode,
+    requests: Vec<RecordedRequest>,
+}
+
+/// Local HTTP server standing in for the OpenAI API, see the [module](crate::testing) documentation.
+///
+/// The server shuts down when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl MockServer {
+    /// Start a server which responds with the responses added by [MockServer::mock].
+    ///
+    /// Requests which match no mock get a 404 error object.
+    ///
+    /// # Panics
+    ///
+    /// If no local port can be bound.
+    pub async fn start() -> Self {
+        Self::with_mode(Mode::Scripted(vec![]))
+    }
+
+    /// Start a server which forwards requests to the API configured by `upstream`,
+    /// and saves each request/response pair to the JSON `fixture` file for [MockServer::replay].
+    ///
+    /// The credentials of `upstream` are not saved, neither are the request headers.
+    ///
+    /// # Panics
+    ///
+    /// If no local port can be bound.
+    pub async fn record<C, P>(fixture: P, upstream: C) -> Self
+    where
+        C: Config + Send + Sync + 'static,
+        P: AsRef<Path>,
+    {
+        let upstream = Upstream {
+            http_client: reqwest::Client::new(),
+            headers: upstream.headers(),
+            query: upstream
+                .query()
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            url: Arc::new(move |path| upstream.url(path)),
+        };
+
+        Self::with_mode(Mode::Record {
+            upstream,
+            fixture: fixture.as_ref().to_path_buf(),
+            exchanges: vec![],
+        })
+    }
+
+    /// Start a server which responds with the responses saved to `fixture` by [MockServer::record].
+    ///
+    /// Each saved response is returned once, to the first request with the same method and path.
+    pub async fn replay<P: AsRef<Path>>(fixture: P) -> Result<Self, OpenAIError> {
+        let bytes = std::fs::read(fixture.as_ref()).map_err(|e| {
+            OpenAIError::FileReadError(format!("{}: {e}", fixture.as_ref().display()))
+        })?;
+        let exchanges: VecDeque<Exchange> = serde_json::from_slice(&bytes)
+            .map_err(|e| map_deserialization_error(e, bytes.as_ref()))?;
+
+        Ok(Self::with_mode(Mode::Replay(exchanges)))
+    }
+
+    /// Replay `fixture` when it exists, otherwise record it from `upstream`.
+    ///
+    /// Delete the fixture file to record it again.
+    pub async fn record_or_replay<C, P>(fixture: P, upstream: C) -> Result<Self, OpenAIError>
+    where
+        C: Config + Send + Sync + 'static,
+        P: AsRef<Path>,
+    {
+        if fixture.as_ref().exists() {
+            Self::replay(fixture).await
+        } else {
+            Ok(Self::record(fixture, upstream).await)
+        }
+    }
+
+    fn with_mode(mode: Mode) -> Self {
+        let state = Arc::new(Mutex::new(State {
+            mode,
+            requests: vec![],
+        }));
+
+        let listener =
+            std::net::TcpListener::bind("127.0.0.1:0").expect("failed to bind mock server");
+        listener
+            .set_nonblocking(true)
+            .expect("failed to bind mock server");
+        let addr = listener.local_addr().expect("failed to bind mock server");
+
+        let service_state = state.clone();
+        let server = Server::from_tcp(listener)
+            .expect("failed to bind mock server")
+            .serve(make_service_fn(move |_| {
+                let state = service_state.clone();
+                async move {
+                    Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request)))
+                }
+            }));
+
+        let (shutdown, signal) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            signal.await.ok();
+        }));
+
+        Self {
+            addr,
+            state,
+            _shutdown: shutdown,
+        }
+    }
+
+    /// Base URL of the server, to be used as the `api_base` of a [Config].
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Configuration of a client making API calls to this server.
+    pub fn config(&self) -> OpenAIConfig {
+        OpenAIConfig::new()
+            .with_api_base(self.url())
+            .with_api_key("sk-mock")
+    }
+
+    /// Client making API calls to this server.
+    pub fn client(&self) -> Client<OpenAIConfig> {
+        Client::with_config(self.config())
+    }
+
+    /// Respond to requests with `method` and `path` with `response`.
+    ///
+    /// Segments of `path` equal to `*` match any segment, for example `/threads/*/runs`.
+    /// Responses added for the same method and path are returned in order, and the last one is repeated,
+    /// so a rate limit error followed by a success exercises the retries of the client.
+    ///
+    /// # Panics
+    ///
+    /// If the server was started in record or replay mode.
+    pub fn mock(&self, method: &str, path: &str, response: MockResponse) -> &Self {
+        let mut state = self.state.lock().unwrap();
+        let Mode::Scripted(mocks) = &mut state.mode else {
+            panic!("responses can only be mocked by a server started with MockServer::start");
+        };
+
+        match mocks
+            .iter_mut()
+            .find(|mock| mock.method.eq_ignore_ascii_case(method) && mock.path == path)
+        {
+            Some(mock) => mock.responses.push_back(response),
+            None => mocks.push(Mock {
+                method: method.to_string(),
+                path: path.to_string(),
+                responses: VecDeque::from([response]),
+            }),
+        }
+        self
+    }
+
+    /// Requests received so far, in the order they were received.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Forget the requests received so far.
+    pub fn reset_requests(&self) {
+        self.state.lock().unwrap().requests.clear();
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let (parts, body) = request.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+    let request = RecordedRequest {
+        method: parts.method,
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(|query| query.to_string()),
+        headers: parts.headers,
+        body,
+    };
+
+    let response = respond(&state, &request).await;
+
+    if let Some(delay) = response.delay {
+        tokio::time::sleep(delay).await;
+    }
+
+    let mut builder = Response::builder().status(response.status);
+    for (name, value) in response.headers.iter() {
+        builder = builder.header(name, value);
+    }
+    if response.chunks.is_empty() {
+        return Ok(builder.body(Body::from(response.body)).unwrap());
+    }
+
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        if sender.send_data(response.body).await.is_err() {
+            return;
+        }
+        for (delay, chunk) in response.chunks {
+            tokio::time::sleep(delay).await;
+            // Client closed the connection
+            if sender.send_data(chunk).await.is_err() {
+                return;
+            }
+        }
+    });
+    Ok(builder.body(body).unwrap())
+}
+
+async fn respond(state: &Mutex<State>, request: &RecordedRequest) -> MockResponse {
+    let upstream = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(request.clone());
+
+        match &mut state.mode {
+            Mode::Scripted(mocks) => {
+                return match mocks
+                    .iter_mut()
+                    .find(|mock| mock.matches(&request.method, &request.path))
+                {
+                    Some(mock) if mock.responses.len() > 1 => mock.responses.pop_front().unwrap(),
+                    Some(mock) => mock.responses[0].clone(),
+                    None => not_found(request),
+                };
+            }
+            Mode::Replay(exchanges) => {
+                return match exchanges.iter().position(|exchange| {
+                    exchange
+                        .method
+                        .eq_ignore_ascii_case(request.method.as_str())
+                        && exchange.path == request.path
+                }) {
+                    Some(index) => exchanges.remove(index).unwrap().response(),
+                    None => not_found(request),
+                };
+            }
+            Mode::Record { upstream, .. } => upstream.clone(),
+        }
+    };
+
+    let exchange = match forward(&upstream, request).await {
+        Ok(exchange) => exchange,
+        Err(e) => return MockResponse::error(502, "server_error", None, &format!("upstream: {e}")),
+    };
+    let response = exchange.response();
+
+    let mut state = state.lock().unwrap();
+    if let Mode::Record {
+        fixture, exchanges, ..
+    } = &mut state.mode
+    {
+        exchanges.push(exchange);
+        let saved = serde_json::to_vec_pretty(exchanges)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&*fixture, json).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            tracing::error!("failed to save fixture {}: {e}", fixture.display());
+        }
+    }
+
+    response
+}
+
+/// Send `request` to the real API, and read its whole response.
+async fn forward(upstream: &Upstream, request: &RecordedRequest) -> Result<Exchange, OpenAIError> {
+    let mut url = (upstream.url)(&request.path);
+    if let Some(query) = &request.query {
+        url = format!("{url}?{query}");
+    }
+
+    let mut builder = upstream
+        .http_client
+        .request(request.method.clone(), url)
+        .query(&upstream.query)
+        .headers(upstream.headers.clone())
+        .body(request.body.clone());
+    if let Some(content_type) = request.headers.get(CONTENT_TYPE) {
+        builder = builder.header(CONTENT_TYPE, content_type);
+    }
+
+    let response = builder.send().await?;
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    let bytes = response.bytes().await?;
+
+    let (body, body_base64) = match std::str::from_utf8(&bytes) {
+        Ok(body) => (Some(body.to_string()), None),
+        Err(_) => (None, Some(general_purpose::STANDARD.encode(&bytes))),
+    };
+
+    Ok(Exchange {
+        method: request.method.to_string(),
+        path: request.path.clone(),
+        query: request.query.clone(),
+        request: serde_json::from_slice(&request.body).ok(),
+        status,
+        content_type,
+        body,
+        body_base64,
+    })
+}
+
+fn not_found(request: &RecordedRequest) -> MockResponse {
+    MockResponse::error(
+        404,
+        "invalid_request_error",
+        None,
+        &format!("no mock response for {} {}", request.method, request.path),
+    )
+}
+
+fn parse_multipart(content_type: &str, body: &[u8]) -> Result<Vec<MultipartField>, OpenAIError> {
+    let boundary = content_type
+        .split(';')
+        .map(str::trim)
+        .find_map(|param| param.strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"'))
+        .ok_or_else(|| {
+            OpenAIError::InvalidArgument(format!("not a multipart body: {content_type}"))
+        })?;
+    let delimiter = format!("--{boundary}");
+
+    let mut fields = vec![];
+    // Content before the first delimiter is a preamble which is ignored.
+    for part in split(body, delimiter.as_bytes()).into_iter().skip(1) {
+        // The last delimiter is followed by `--`
+        if part.starts_with(b"--") {
+            break;
+        }
+        let part = part.strip_prefix(b"\r\n").unwrap_or(part);
+        let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+
+        let header_end = find(part, b"\r\n\r\n").ok_or_else(|| {
+            OpenAIError::InvalidArgument("multipart field without headers".into())
+        })?;
+        let headers = String::from_utf8_lossy(&part[..header_end]);
+
+        let mut field = MultipartField {
+            name: String::new(),
+            file_name: None,
+            content_type: None,
+            data: Bytes::copy_from_slice(&part[header_end + 4..]),
+        };
+        for header in headers.split("\r\n") {
+            let Some((name, value)) = header.split_once(':') else {
+                continue;
+            };
+            if name.trim().eq_ignore_ascii_case("content-type") {
+                field.content_type = Some(value.trim().to_string());
+            } else if name.trim().eq_ignore_ascii_case("content-disposition") {
+                for param in value.split(';').map(str::trim) {
+                    if let Some(name) = param.strip_prefix("name=") {
+                        field.name = name.trim_matches('"').to_string();
+                    } else if let Some(file_name) = param.strip_prefix("filename=") {
+                        field.file_name = Some(file_name.trim_matches('"').to_string());
+                    }
+                }
+            }
+        }
+        fields.push(field);
+    }
+
+    Ok(fields)
+}
+
+fn split<'a>(mut bytes: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut parts = vec![];
+    while let Some(index) = find(bytes, delimiter) {
+        parts.push(&bytes[..index]);
+        bytes = &bytes[index + delimiter.len()..];
+    }
+    parts.push(bytes);
+    parts
+}
+
+fn find(bytes: &[u8], needle: &[u8]) -> Option<usize> {
+    bytes
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use super::{MockResponse, MockServer};
+    use crate::{
+        error::{ApiErrorKind, OpenAIError},
+        types::{
+            AudioInput, ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs,
+            CreateTranscriptionRequestArgs,
+        },
+    };
+
+    fn chat_request(stream: bool) -> crate::types::CreateChatCompletionRequest {
+        CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .messages([ChatCompletionRequestUserMessageArgs::default()
+                .content("Hello")
+                .build()
+                .unwrap()
+                .into()])
+            .stream(stream)
+            .build()
+            .unwrap()
+    }
+
+    fn chat_completion(content: &str) -> serde_json::Value {
+        json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "gpt-3.5-turbo",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": content},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 8, "completion_tokens": 2, "total_tokens": 10}
+        })
+    }
+
+    #[tokio::test]
+    async fn test_scripted_responses() {
+        let server = MockServer::start().await;
+        server
+            .mock(
+                "POST",
+                "/chat/completions",
+                MockResponse::error(400, "invalid_request_error", Some("model_not_found"), "")
+                    .with_header("x-request-id", "req_1"),
+            )
+            .mock(
+                "POST",
+                "/chat/completions",
+                MockResponse::json(&chat_completion("Hi")),
+            );
+        let client = server.client();
+
+        let Err(OpenAIError::ApiError(error)) = client.chat().create(chat_request(false)).await
+        else {
+            panic!("expected an API error");
+        };
+        assert_eq!(error.kind(), Some(ApiErrorKind::ModelNotFound));
+        assert_eq!(error.status().map(|status| status.as_u16()), Some(400));
+        assert_eq!(error.request_id(), Some("req_1"));
+
+        for _ in 0..2 {
+            let response = client.chat().create(chat_request(false)).await.unwrap();
+            assert_eq!(response.choices[0].message.content.as_deref(), Some("Hi"));
+        }
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].header("authorization"), Some("Bearer sk-mock"));
+        assert_eq!(
+            requests[0].json::<serde_json::Value>().unwrap()["model"],
+            "gpt-3.5-turbo"
+        );
+
+        assert!(client.models().list().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sse_response() {
+        let server = MockServer::start().await;
+        let chunk = |content: &str| {
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": "gpt-3.5-turbo",
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+            })
+        };
+        server.mock(
+            "POST",
+            "/chat/completions",
+            MockResponse::sse([chunk("Hello"), chunk(" world")]),
+        );
+
+        let mut stream = server
+            .client()
+            .chat()
+            .create_stream(chat_request(true))
+            .await
+            .unwrap();
+        let mut content = String::new();
+        while let Some(chunk) = stream.next().await {
+            content.push_str(chunk.unwrap().choices[0].delta.content.as_deref().unwrap());
+        }
+        assert_eq!(content, "Hello world");
+    }
+
+    #[tokio::test]
+    async fn test_multipart_request() {
+        let server = MockServer::start().await;
+        server.mock(
+            "POST",
+            "/audio/transcriptions",
+            MockResponse::json(&json!({"text": "Hello"})),
+        );
+
+        let request = CreateTranscriptionRequestArgs::default()
+            .file(AudioInput::from_vec_u8("audio.mp3".into(), vec![1, 2, 3]))
+            .model("whisper-1")
+            .build()
+            .unwrap();
+        let response = server.client().audio().transcribe(request).await.unwrap();
+        assert_eq!(response.text, "Hello");
+
+        let fields = server.requests()[0].multipart().unwrap();
+        let file = fields.iter().find(|field| field.name == "file").unwrap();
+        assert_eq!(file.file_name.as_deref(), Some("audio.mp3"));
+        assert_eq!(file.data.as_ref(), &[1, 2, 3]);
+        let model = fields.iter().find(|field| field.name == "model").unwrap();
+        assert_eq!(model.text(), "whisper-1");
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let fixture =
+            std::env::temp_dir().join(format!("async-openai-fixture-{}.json", std::process::id()));
+
+        let upstream = MockServer::start().await;
+        upstream.mock(
+            "POST",
+            "/chat/completions",
+            MockResponse::json(&chat_completion("Recorded")),
+        );
+
+        let recorder = MockServer::record(&fixture, upstream.config()).await;
+        let response = recorder
+            .client()
+            .chat()
+            .create(chat_request(false))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.choices[0].message.content.as_deref(),
+            Some("Recorded")
+        );
+        assert_eq!(
+            upstream.requests()[0].header("authorization"),
+            Some("Bearer sk-mock")
+        );
+        drop(upstream);
+
+        let replay = MockServer::replay(&fixture).await.unwrap();
+        let client = replay.client();
+        let response = client.chat().create(chat_request(false)).await.unwrap();
+        assert_eq!(
+            response.choices[0].message.content.as_deref(),
+            Some("Recorded")
+        );
+        // Every recorded response is replayed once
+        assert!(client.chat().create(chat_request(false)).await.is_err());
+
+        std::fs::remove_file(fixture).unwrap();
+    }
+}
@@ -0,0 +1,502 @@
+//! Count tokens locally with the BPE tables of OpenAI models, available with the `tokenizer` feature.
+//!
+//! Chat counts follow the [per message overhead](https://github.com/openai/openai-cookbook/blob/main/examples/How_to_count_tokens_with_tiktoken.ipynb)
+//! of chat models. Tool definitions are counted as the model sees them, rendered as TypeScript declarations
+//! in the system prompt, which is close to but not always exactly what the API bills.
+use std::sync::OnceLock;
+
+use serde_json::Value;
+use tiktoken_rs::CoreBPE;
+
+use crate::{
+    error::OpenAIError,
+    types::{
+        ChatCompletionRequestMessage, ChatCompletionRequestMessageContentPart,
+        ChatCompletionRequestUserMessageContent, ChatCompletionTool,
+        ChatCompletionToolChoiceOption, CreateChatCompletionRequest, EmbeddingInput,
+        FunctionObject, Prompt,
+    },
+    util::{TOKENS_PER_IMAGE, TOKENS_PER_MESSAGE},
+};
+
+/// BPE encoding used by a family of models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// `gpt-4o` models.
+    O200kBase,
+    /// `gpt-4`, `gpt-3.5-turbo`, `text-embedding-ada-002` and `text-embedding-3-*` models.
+    Cl100kBase,
+    /// Code models, `text-davinci-002` and `text-davinci-003`.
+    P50kBase,
+    /// GPT-3 models like `davinci`, also known as `gpt2`.
+    R50kBase,
+}
+
+impl Encoding {
+    /// Encoding of `model`, fine-tuned models (`ft:gpt-3.5-turbo:...`) use the encoding of their base model.
+    pub fn for_model(model: &str) -> Option<Self> {
+        let model = model.strip_prefix("ft:").unwrap_or(model);
+
+        const O200K_BASE: &[&str] = &["gpt-4o"];
+        const CL100K_BASE: &[&str] = &[
+            "gpt-4",
+            "gpt-3.5-turbo",
+            "gpt-35-turbo",
+            "text-embedding-ada-002",
+            "text-embedding-3-",
+            "davinci-002",
+            "babbage-002",
+        ];
+        const P50K_BASE: &[&str] = &["text-davinci-002", "text-davinci-003", "code-"];
+        const R50K_BASE: &[&str] = &[
+            "text-davinci-001",
+            "text-curie-",
+            "text-babbage-",
+            "text-ada-",
+            "davinci",
+            "curie",
+            "babbage",
+            "ada",
+            "text-similarity-",
+            "text-search-",
+        ];
+
+        let matches = |prefixes: &[&str]| prefixes.iter().any(|prefix| model.starts_with(prefix));
+        if matches(O200K_BASE) {
+            Some(Self::O200kBase)
+        } else if matches(CL100K_BASE) {
+            Some(Self::Cl100kBase)
+        } else if matches(P50K_BASE) {
+            Some(Self::P50kBase)
+        } else if matches(R50K_BASE) {
+            Some(Self::R50kBase)
+        } else {
+            None
+        }
+    }
+}
+
+/// Counts tokens of text, chat messages and embedding inputs with an [Encoding].
+///
+/// ```
+/// use async_openai::{tokenizer::Tokenizer, types::ChatCompletionRequestUserMessageArgs};
+///
+/// # fn main() -> Result<(), async_openai::error::OpenAIError> {
+/// let tokenizer = Tokenizer::for_model("gpt-3.5-turbo")?;
+///
+/// assert_eq!(tokenizer.count("Hello world!"), 3);
+///
+/// let messages = [ChatCompletionRequestUserMessageArgs::default()
+///     .content("Hello world!")
+///     .build()?
+///     .into()];
+/// assert_eq!(tokenizer.count_messages(&messages, None), 10);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tokenizer {
+    encoding: Encoding,
+}
+
+/// Tokens of the name of a message, in addition to the name itself.
+const TOKENS_PER_NAME: usize = 1;
+
+/// Every reply is primed with `<|start|>assistant<|message|>`.
+const TOKENS_PER_REPLY: usize = 3;
+
+/// Tokens of the declaration of tools, in addition to their definitions.
+const TOKENS_PER_TOOLS: usize = 9;
+
+impl Tokenizer {
+    pub fn new(encoding: Encoding) -> Self {
+        Self { encoding }
+    }
+
+    /// Tokenizer of the encoding of `model`, fails with [OpenAIError::InvalidArgument] for unknown models.
+    pub fn for_model(model: &str) -> Result<Self, OpenAIError> {
+        Encoding::for_model(model)
+            .map(Self::new)
+            .ok_or_else(|| OpenAIError::InvalidArgument(format!("no known encoding for {model}")))
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Token ids of `text`.
+    pub fn encode(&self, text: &str) -> Vec<usize> {
+        self.bpe().encode_with_special_tokens(text)
+    }
+
+    /// BPE of the encoding, built on first use and shared by all tokenizers without locking.
+    fn bpe(&self) -> &'static CoreBPE {
+        static O200K_BASE: OnceLock<CoreBPE> = OnceLock::new();
+        static CL100K_BASE: OnceLock<CoreBPE> = OnceLock::new();
+        static P50K_BASE: OnceLock<CoreBPE> = OnceLock::new();
+        static R50K_BASE: OnceLock<CoreBPE> = OnceLock::new();
+
+        // The tables are bundled with tiktoken-rs, so building them doesn't fail
+        match self.encoding {
+            Encoding::O200kBase => O200K_BASE.get_or_init(|| tiktoken_rs::o200k_base().unwrap()),
+            Encoding::Cl100kBase => CL100K_BASE.get_or_init(|| tiktoken_rs::cl100k_base().unwrap()),
+            Encoding::P50kBase => P50K_BASE.get_or_init(|| tiktoken_rs::p50k_base().unwrap()),
+            Encoding::R50kBase => R50K_BASE.get_or_init(|| tiktoken_rs::r50k_base().unwrap()),
+        }
+    }
+
+    /// Number of tokens of `text`.
+    pub fn count(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+
+    /// Prompt tokens of a chat conversation, including the priming of the reply
+    /// and the definitions of `tools` when the request has any.
+    #[allow(deprecated)]
+    pub fn count_messages(
+        &self,
+        messages: &[ChatCompletionRequestMessage],
+        tools: Option<&[ChatCompletionTool]>,
+    ) -> usize {
+        let tools = tools.filter(|tools| !tools.is_empty());
+        let mut tokens = TOKENS_PER_REPLY;
+
+        for message in messages {
+            tokens += self.count_message(message);
+        }
+
+        if let Some(tools) = tools {
+            let functions: Vec<&FunctionObject> = tools.iter().map(|tool| &tool.function).collect();
+            tokens += self.count(&format_functions(&functions)) + TOKENS_PER_TOOLS;
+
+            // Tool definitions are merged with the system message
+            if messages
+                .iter()
+                .any(|message| matches!(message, ChatCompletionRequestMessage::System(_)))
+            {
+                tokens = tokens.saturating_sub(4);
+            }
+        }
+
+        tokens
+    }
+
+    /// Tokens of a single message of a conversation, including the chat format around it.
+    #[allow(deprecated)]
+    pub fn count_message(&self, message: &ChatCompletionRequestMessage) -> usize {
+        let (role, name, content) = match message {
+            ChatCompletionRequestMessage::System(message) => (
+                message.role,
+                message.name.as_deref(),
+                self.count(&message.content),
+            ),
+            ChatCompletionRequestMessage::User(message) => {
+                let content = match &message.content {
+                    ChatCompletionRequestUserMessageContent::Text(text) => self.count(text),
+                    ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                        .iter()
+                        .map(|part| match part {
+                            ChatCompletionRequestMessageContentPart::Text(text) => {
+                                self.count(&text.text)
+                            }
+                            ChatCompletionRequestMessageContentPart::Image(_) => TOKENS_PER_IMAGE,
+                        })
+                        .sum(),
+                };
+                (message.role, message.name.as_deref(), content)
+            }
+            ChatCompletionRequestMessage::Assistant(message) => {
+                let mut content = message.content.as_deref().map_or(0, |c| self.count(c));
+                for call in message.tool_calls.iter().flatten() {
+                    content += self.count(&call.function.name)
+                        + self.count(&call.function.arguments)
+                        + TOKENS_PER_MESSAGE;
+                }
+                if let Some(call) = &message.function_call {
+                    content +=
+                        self.count(&call.name) + self.count(&call.arguments) + TOKENS_PER_MESSAGE;
+                }
+                (message.role, message.name.as_deref(), content)
+            }
+            ChatCompletionRequestMessage::Tool(message) => {
+                (message.role, None, self.count(&message.content))
+            }
+            ChatCompletionRequestMessage::Function(message) => (
+                message.role,
+                Some(message.name.as_str()),
+                message.content.as_deref().map_or(0, |c| self.count(c)),
+            ),
+        };
+
+        TOKENS_PER_MESSAGE
+            + self.count(&role.to_string())
+            + content
+            + name.map_or(0, |name| self.count(name) + TOKENS_PER_NAME)
+    }
+
+    /// Prompt tokens of a chat completion request: messages, tools and a forced tool choice.
+    #[allow(deprecated)]
+    pub fn count_request(&self, request: &CreateChatCompletionRequest) -> usize {
+        let mut tokens = self.count_messages(&request.messages, request.tools.as_deref());
+
+        if request.tools.is_none() {
+            if let Some(functions) = request.functions.as_ref().filter(|f| !f.is_empty()) {
+                let functions: Vec<FunctionObject> = functions
+                    .iter()
+                    .map(|function| FunctionObject {
+                        name: function.name.clone(),
+                        description: function.description.clone(),
+                        parameters: Some(function.parameters.clone()),
+                    })
+                    .collect();
+                let functions: Vec<&FunctionObject> = functions.iter().collect();
+                tokens += self.count(&format_functions(&functions)) + TOKENS_PER_TOOLS;
+            }
+        }
+
+        match &request.tool_choice {
+            Some(ChatCompletionToolChoiceOption::None) => tokens += 1,
+            Some(ChatCompletionToolChoiceOption::Named(named)) => {
+                tokens += self.count(&named.function.name) + 4
+            }
+            _ => {}
+        }
+
+        tokens
+    }
+
+    /// Tokens of the prompt of a legacy completion.
+    pub fn count_prompt(&self, prompt: &Prompt) -> usize {
+        match prompt {
+            Prompt::String(text) => self.count(text),
+            Prompt::StringArray(texts) => texts.iter().map(|text| self.count(text)).sum(),
+            Prompt::IntegerArray(tokens) => tokens.len(),
+            Prompt::ArrayOfIntegerArray(tokens) => tokens.iter().map(|t| t.len()).sum(),
+        }
+    }
+
+    /// Tokens of all inputs of an embedding request.
+    pub fn count_embedding_input(&self, input: &EmbeddingInput) -> usize {
+        match input {
+            EmbeddingInput::String(text) => self.count(text),
+            EmbeddingInput::StringArray(texts) => texts.iter().map(|text| self.count(text)).sum(),
+            EmbeddingInput::IntegerArray(tokens) => tokens.len(),
+            EmbeddingInput::ArrayOfIntegerArray(tokens) => tokens.iter().map(|t| t.len()).sum(),
+        }
+    }
+}
+
+/// Function definitions as the model sees them.
+fn format_functions(functions: &[&FunctionObject]) -> String {
+    let mut lines = vec!["namespace functions {".to_string(), String::new()];
+
+    for function in functions {
+        if let Some(description) = &function.description {
+            lines.push(format!("// {description}"));
+        }
+
+        let parameters = function.parameters.as_ref();
+        let has_properties = parameters
+            .and_then(|parameters| parameters.get("properties"))
+            .and_then(Value::as_object)
+            .is_some_and(|properties| !properties.is_empty());
+
+        if let (true, Some(parameters)) = (has_properties, parameters) {
+            lines.push(format!("type {} = (_: {{", function.name));
+            lines.push(format_properties(parameters, 0));
+            lines.push("}) => any;".to_string());
+        } else {
+            lines.push(format!("type {} = () => any;", function.name));
+        }
+        lines.push(String::new());
+    }
+
+    lines.push("} // namespace functions".to_string());
+    lines.join("\n")
+}
+
+fn format_properties(object: &Value, indent: usize) -> String {
+    let required: Vec<&str> = object
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut lines = vec![];
+    for (name, property) in object
+        .get("properties")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+    {
+        if let (Some(description), true) = (
+            property.get("description").and_then(Value::as_str),
+            indent < 2,
+        ) {
+            lines.push(format!("// {description}"));
+        }
+        let optional = if required.contains(&name.as_str()) {
+            ""
+        } else {
+            "?"
+        };
+        lines.push(format!(
+            "{name}{optional}: {},",
+            format_type(property, indent)
+        ));
+    }
+
+    lines
+        .iter()
+        .map(|line| format!("{}{line}", " ".repeat(indent)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_type(property: &Value, indent: usize) -> String {
+    let enumeration = |quote: &str| {
+        property
+            .get("enum")
+            .and_then(Value::as_array)
+            .map(|values| {
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(value) => format!("{quote}{value}{quote}"),
+                        value => value.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+            })
+    };
+
+    match property.get("type").and_then(Value::as_str) {
+        Some("string") => enumeration("\"").unwrap_or_else(|| "string".into()),
+        Some(r#type @ ("number" | "integer")) => {
+            enumeration("").unwrap_or_else(|| r#type.to_string())
+        }
+        Some("boolean") => "boolean".into(),
+        Some("null") => "null".into(),
+        Some("object") => format!("{{\n{}\n}}", format_properties(property, indent + 2)),
+        Some("array") => match property.get("items") {
+            Some(items) => format!("{}[]", format_type(items, indent)),
+            None => "any[]".into(),
+        },
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Encoding, Tokenizer};
+    use crate::types::{
+        ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+        ChatCompletionToolArgs, CreateChatCompletionRequestArgs, EmbeddingInput,
+        FunctionObjectArgs,
+    };
+
+    #[test]
+    fn test_encoding_for_model() {
+        assert_eq!(
+            Encoding::for_model("gpt-4-1106-preview"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("ft:gpt-3.5-turbo-0613:org::8abc"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("text-davinci-003"),
+            Some(Encoding::P50kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("gpt-4o-2024-05-13"),
+            Some(Encoding::O200kBase)
+        );
+        assert_eq!(
+            Encoding::for_model("gpt-4-turbo"),
+            Some(Encoding::Cl100kBase)
+        );
+        assert_eq!(Encoding::for_model("davinci"), Some(Encoding::R50kBase));
+        assert_eq!(Encoding::for_model("whisper-1"), None);
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        let tokenizer = Tokenizer::for_model("gpt-3.5-turbo").unwrap();
+
+        // Example of the OpenAI cookbook, counted as 129 prompt tokens by the API
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .messages([
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .content("You are a helpful, pattern-following assistant that translates corporate jargon into plain English.")
+                    .build()
+                    .unwrap()
+                    .into(),
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .name("example_user")
+                    .content("New synergies will help drive top-line growth.")
+                    .build()
+                    .unwrap()
+                    .into(),
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .name("example_assistant")
+                    .content("Things working well together will increase revenue.")
+                    .build()
+                    .unwrap()
+                    .into(),
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .name("example_user")
+                    .content("Let's circle back when we have more bandwidth to touch base on opportunities for increased leverage.")
+                    .build()
+                    .unwrap()
+                    .into(),
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .name("example_assistant")
+                    .content("Let's talk later when we're less busy about how to do better.")
+                    .build()
+                    .unwrap()
+                    .into(),
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content("This late pivot means we don't have time to boil the ocean for the client deliverable.")
+                    .build()
+                    .unwrap()
+                    .into(),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(tokenizer.count_request(&request), 129);
+
+        let tool = ChatCompletionToolArgs::default()
+            .function(
+                FunctionObjectArgs::default()
+                    .name("get_current_weather")
+                    .description("Get the current weather in a given location")
+                    .parameters(json!({
+                        "type": "object",
+                        "properties": {
+                            "location": {"type": "string", "description": "The city and state, e.g. San Francisco, CA"},
+                            "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                        },
+                        "required": ["location"],
+                    }))
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        let with_tools = tokenizer.count_messages(&request.messages, Some(&[tool]));
+        assert!(with_tools > tokenizer.count_messages(&request.messages, None));
+
+        assert_eq!(
+            tokenizer.count_embedding_input(&EmbeddingInput::StringArray(vec![
+                "Hello world!".into(),
+                "Hello".into()
+            ])),
+            4
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Run Rust functions as tools called by the model, see [crate::Chat::create_with_tools].
+use std::{
+    any::Any, collections::HashMap, fmt, future::Future, panic::AssertUnwindSafe, pin::Pin,
+    sync::Arc,
+};
+
+use futures::FutureExt;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::types::{
+    ChatCompletionMessageToolCall, ChatCompletionRequestMessage, ChatCompletionRequestToolMessage,
+    ChatCompletionTool, ChatCompletionToolType, CreateChatCompletionResponse, FunctionCall,
+    FunctionObject, Role, RunToolCallObject, ToolsOutputs,
+};
+
+/// Type erased handler: receives the JSON arguments generated by the model,
+/// returns the JSON result, or a description of the failure.
+type ToolHandler = Arc<
+    dyn Fn(String) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send>> + Send + Sync,
+>;
+
+/// Arguments of a function called by the model, described to the model by a JSON Schema.
+///
+/// With the `schemars` feature it is implemented for all types deriving [schemars::JsonSchema] and [serde::Deserialize],
+/// the doc comment of the type becomes the description of the function.
+///
+/// ```
+/// # #[cfg(feature = "schemars")]
+/// # fn main() -> Result<(), async_openai::error::OpenAIError> {
+/// use async_openai::{tools::FunctionParameters, types::{ChatCompletionTool, FunctionCall}};
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+///
+/// /// Get the current weather in a given location
+/// #[derive(Deserialize, JsonSchema)]
+/// struct GetCurrentWeather {
+///     /// The city and state, e.g. San Francisco, CA
+///     location: String,
+///     unit: Option<String>,
+/// }
+///
+/// let tool = ChatCompletionTool::from_parameters::<GetCurrentWeather>("get_current_weather");
+///
+/// let call = FunctionCall {
+///     name: "get_current_weather".into(),
+///     arguments: r#"{"location": "Boston, MA"}"#.into(),
+/// };
+/// let args: GetCurrentWeather = call.parse_arguments()?;
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "schemars"))]
+/// # fn main() {}
+/// ```
+pub trait FunctionParameters: DeserializeOwned {
+    /// JSON Schema object of the parameters, as set in [FunctionObject::parameters].
+    fn parameters_schema() -> serde_json::Value;
+
+    /// Description of the function, as set in [FunctionObject::description].
+    fn description() -> Option<String> {
+        None
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl<T> FunctionParameters for T
+where
+    T: schemars::JsonSchema + DeserializeOwned,
+{
+    fn parameters_schema() -> serde_json::Value {
+        let mut schema = serde_json::to_value(parameters_root_schema::<T>().schema)
+            .unwrap_or(serde_json::Value::Null);
+        if let Some(object) = schema.as_object_mut() {
+            object.remove("title");
+            object.remove("description");
+        }
+        schema
+    }
+
+    fn description() -> Option<String> {
+        parameters_root_schema::<T>()
+            .schema
+            .metadata
+            .and_then(|metadata| metadata.description)
+    }
+}
+
+/// Schema with subschemas inlined, as the API doesn't resolve references to definitions.
+#[cfg(feature = "schemars")]
+fn parameters_root_schema<T: schemars::JsonSchema>() -> schemars::schema::RootSchema {
+    schemars::gen::SchemaSettings::draft07()
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.meta_schema = None;
+        })
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+/// Async Rust functions registered by name, along with the [ChatCompletionTool]s describing them to the model.
+///
+/// Arguments generated by the model are deserialized into the argument type of the handler,
+/// and the value returned by the handler is serialized to JSON as the content of the tool message.
+/// Unknown tools, invalid arguments, handler errors and panics are reported to the model in the tool message
+/// as `{"error": "..."}`, so that it can correct itself.
+///
+/// ```
+/// use async_openai::{tools::ToolRegistry, types::FunctionObjectArgs};
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct WeatherArgs {
+///     location: String,
+/// }
+///
+/// # fn main() -> Result<(), async_openai::error::OpenAIError> {
+/// let tools = ToolRegistry::new().with_tool(
+///     FunctionObjectArgs::default()
+///         .name("get_current_weather")
+///         .description("Get the current weather in a given location")
+///         .parameters(json!({
+///             "type": "object",
+///             "properties": { "location": { "type": "string" } },
+///             "required": ["location"],
+///         }))
+///         .build()?,
+///     |args: WeatherArgs| async move {
+///         Ok::<_, std::io::Error>(json!({ "location": args.location, "temperature": 22 }))
+///     },
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<ChatCompletionTool>,
+    handlers: HashMap<String, ToolHandler>,
+}
+
+impl fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.tools)
+            .finish()
+    }
+}
+
+/// Final response of [crate::Chat::create_with_tools], along with the conversation which led to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolLoopResponse {
+    /// Response of the model once it stopped calling tools.
+    pub response: CreateChatCompletionResponse,
+    /// Messages of the request, followed by the tool calls, their results, and the final message of the model.
+    pub messages: Vec<ChatCompletionRequestMessage>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Register `handler` to run when the model calls `function`, a tool registered with the same name is replaced.
+    pub fn with_tool<A, F, Fut, R, E>(mut self, function: FunctionObject, handler: F) -> Self
+    where
+        A: DeserializeOwned + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, E>> + Send + 'static,
+        R: Serialize,
+        E: fmt::Display,
+    {
+        let handler = Arc::new(handler);
+        let tool_handler: ToolHandler = Arc::new(move |arguments: String| {
+            let handler = handler.clone();
+            Box::pin(async move {
+                // Functions without parameters may get empty arguments
+                let arguments = if arguments.trim().is_empty() {
+                    "{}"
+                } else {
+                    arguments.as_str()
+                };
+                let args: A = serde_json::from_str(arguments)
+                    .map_err(|e| format!("invalid arguments: {e}"))?;
+                let output = handler(args).await.map_err(|e| e.to_string())?;
+                serde_json::to_string(&output).map_err(|e| format!("invalid output: {e}"))
+            })
+        });
+
+        self.tools
+            .retain(|tool| tool.function.name != function.name);
+        self.handlers.insert(function.name.clone(), tool_handler);
+        self.tools.push(ChatCompletionTool {
+            r#type: ChatCompletionToolType::Function,
+            function,
+        });
+        self
+    }
+
+    /// Register `handler` as the function `name`, described to the model by the [FunctionParameters] of its argument type.
+    pub fn with_function<A, F, Fut, R, E>(self, name: &str, handler: F) -> Self
+    where
+        A: FunctionParameters + Send + 'static,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, E>> + Send + 'static,
+        R: Serialize,
+        E: fmt::Display,
+    {
+        self.with_tool(FunctionObject::from_parameters::<A>(name), handler)
+    }
+
+    /// Tools to set on the request, in the order they were registered.
+    pub fn tools(&self) -> Vec<ChatCompletionTool> {
+        self.tools.clone()
+    }
+
+    /// Run the handler of a tool call and return its result as a tool message.
+    pub async fn call(
+        &self,
+        tool_call: &ChatCompletionMessageToolCall,
+    ) -> ChatCompletionRequestToolMessage {
+        ChatCompletionRequestToolMessage {
+            role: Role::Tool,
+            content: self.output(&tool_call.function).await,
+            tool_call_id: tool_call.id.clone(),
+        }
+    }
+
+    /// Run the handlers of all tool calls concurrently, tool messages are in the order of `tool_calls`.
+    pub async fn call_all(
+        &self,
+        tool_calls: &[ChatCompletionMessageToolCall],
+    ) -> Vec<ChatCompletionRequestToolMessage> {
+        futures::future::join_all(tool_calls.iter().map(|tool_call| self.call(tool_call))).await
+    }
+
+    /// Run the handler of a tool call required by an Assistants run, and return its result as a tool output.
+    pub async fn call_run_tool(&self, tool_call: &RunToolCallObject) -> ToolsOutputs {
+        ToolsOutputs {
+            tool_call_id: Some(tool_call.id.clone()),
+            output: Some(self.output(&tool_call.function).await),
+        }
+    }
+
+    /// Run the handlers of all tool calls required by an Assistants run concurrently,
+    /// tool outputs are in the order of `tool_calls`, see [crate::Runs::poll_with_tools].
+    pub async fn call_run_tools(&self, tool_calls: &[RunToolCallObject]) -> Vec<ToolsOutputs> {
+        futures::future::join_all(
+            tool_calls
+                .iter()
+                .map(|tool_call| self.call_run_tool(tool_call)),
+        )
+        .await
+    }
+
+    /// Result of the handler of `function` as JSON, or the failure as `{"error": "..."}`.
+    async fn output(&self, function: &FunctionCall) -> String {
+        let output = match self.handlers.get(&function.name) {
+            Some(handler) => AssertUnwindSafe(handler(function.arguments.clone()))
+                .catch_unwind()
+                .await
+                .unwrap_or_else(|panic| Err(format!("tool panicked: {}", panic_message(&*panic)))),
+            None => Err(format!("unknown tool: {}", function.name)),
+        };
+
+        output.unwrap_or_else(|error| {
+            tracing::warn!("Tool call {} failed: {error}", function.name);
+            serde_json::json!({ "error": error }).to_string()
+        })
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::ToolRegistry;
+    use crate::types::{
+        ChatCompletionMessageToolCall, ChatCompletionToolType, FunctionCall, FunctionObjectArgs,
+    };
+
+    #[derive(Deserialize)]
+    struct AddArgs {
+        a: i64,
+        b: i64,
+    }
+
+    fn tool_call(id: &str, name: &str, arguments: &str) -> ChatCompletionMessageToolCall {
+        ChatCompletionMessageToolCall {
+            id: id.into(),
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionCall {
+                name: name.into(),
+                arguments: arguments.into(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_registry() {
+        let tools = ToolRegistry::new().with_tool(
+            FunctionObjectArgs::default().name("add").build().unwrap(),
+            |args: AddArgs| async move {
+                if args.a < 0 {
+                    return Err("negative numbers are not supported");
+                }
+                Ok(args.a + args.b)
+            },
+        );
+        assert_eq!(tools.tools().len(), 1);
+
+        let messages = tools
+            .call_all(&[
+                tool_call("call_1", "add", r#"{"a": 1, "b": 2}"#),
+                tool_call("call_2", "add", r#"{"a": -1, "b": 2}"#),
+                tool_call("call_3", "add", r#"{"a": "one"}"#),
+                tool_call("call_4", "subtract", "{}"),
+            ])
+            .await;
+
+        assert_eq!(messages[0].tool_call_id, "call_1");
+        assert_eq!(messages[0].content, "3");
+        assert_eq!(
+            messages[1].content,
+            r#"{"error":"negative numbers are not supported"}"#
+        );
+        assert!(messages[2].content.contains("invalid arguments"));
+        assert_eq!(messages[3].content, r#"{"error":"unknown tool: subtract"}"#);
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_function_parameters_schema() {
+        use super::FunctionParameters;
+
+        #[allow(dead_code)]
+        #[derive(Deserialize, schemars::JsonSchema)]
+        enum Unit {
+            Celsius,
+            Fahrenheit,
+        }
+
+        /// Get the current weather in a given location
+        #[allow(dead_code)]
+        #[derive(Deserialize, schemars::JsonSchema)]
+        struct GetCurrentWeather {
+            /// The city and state, e.g. San Francisco, CA
+            location: String,
+            unit: Option<Unit>,
+        }
+
+        assert_eq!(
+            GetCurrentWeather::description().as_deref(),
+            Some("Get the current weather in a given location")
+        );
+
+        let schema = GetCurrentWeather::parameters_schema();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], serde_json::json!(["location"]));
+        assert_eq!(
+            schema["properties"]["location"]["description"],
+            "The city and state, e.g. San Francisco, CA"
+        );
+        assert!(schema.get("title").is_none());
+        assert!(schema.get("definitions").is_none());
+        assert!(!schema.to_string().contains("$ref"));
+    }
+}
@@ -86,6 +86,9 @@ pub struct CreateTranscriptionRequest {
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct CreateTranscriptionResponse {
     pub text: String,
+    /// Duration of the input audio in seconds, only returned with the `verbose_json` response format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f32>,
 }
 
 #[derive(Clone, Default, Debug, Builder, PartialEq, Serialize)]
@@ -139,6 +142,9 @@ pub struct CreateTranslationRequest {
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
 pub struct CreateTranslationResponse {
     pub text: String,
+    /// Duration of the input audio in seconds, only returned with the `verbose_json` response format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f32>,
 }
 
 #[derive(Debug, Clone)]
@@ -331,6 +331,13 @@ pub enum ChatCompletionResponseFormatType {
     JsonObject,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct ChatCompletionStreamOptions {
+    /// If set, an additional chunk is streamed before the `data: [DONE]` message, with the usage statistics for the entire request
+    /// in its `usage` field and an empty `choices` array.
+    pub include_usage: bool,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct ChatCompletionResponseFormat {
     /// Setting to `json_object` enables JSON mode. This guarantees that the message the model generates is valid JSON.
@@ -472,6 +479,10 @@ pub struct CreateChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
 
+    /// Options for the streamed response, only set this when `stream` is `true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<ChatCompletionStreamOptions>,
+
     /// What sampling temperature to use, between 0 and 2. Higher values like 0.8 will make the output more random,
     /// while lower values like 0.2 will make it more focused and deterministic.
     ///
@@ -653,4 +664,6 @@ pub struct CreateChatCompletionStreamResponse {
     pub system_fingerprint: Option<String>,
     /// The object type, which is always `chat.completion.chunk`.
     pub object: String,
+    /// Usage statistics for the entire request, only on the last chunk when [ChatCompletionStreamOptions::include_usage] is set.
+    pub usage: Option<CompletionUsage>,
 }
@@ -29,6 +29,19 @@ pub struct CreateFileRequest {
     pub purpose: String,
 }
 
+/// Query parameters of [Files::list](crate::Files::list).
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Builder, PartialEq)]
+#[builder(name = "ListFilesQueryArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ListFilesQuery {
+    /// Only return files with the given purpose.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<OpenAIFilePurpose>,
+}
+
 #[derive(Debug, Deserialize, Clone, PartialEq, Serialize)]
 pub struct ListFilesResponse {
     pub object: String,
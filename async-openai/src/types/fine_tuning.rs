@@ -131,6 +131,28 @@ pub struct FineTuningJob {
     pub validation_file: Option<String>,
 }
 
+/// Query parameters of [FineTuning::list_paginated](crate::FineTuning::list_paginated) and [FineTuning::list_events](crate::FineTuning::list_events).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, Builder, PartialEq)]
+#[builder(name = "ListFineTuningJobsQueryArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ListFineTuningJobsQuery {
+    /// Identifier for the last job (or event) from the previous pagination request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+
+    /// Number of jobs (or events) to retrieve. Defaults to 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u8>,
+}
+
+/// Query parameters of [FineTuning::list_events](crate::FineTuning::list_events).
+pub type ListFineTuningJobEventsQuery = ListFineTuningJobsQuery;
+/// Builder of [ListFineTuningJobEventsQuery].
+pub type ListFineTuningJobEventsQueryArgs = ListFineTuningJobsQueryArgs;
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct ListPaginatedFineTuningJobsResponse {
     pub data: Vec<FineTuningJob>,
@@ -142,6 +164,8 @@ pub struct ListPaginatedFineTuningJobsResponse {
 pub struct ListFineTuningJobEventsResponse {
     pub data: Vec<FineTuningJobEvent>,
     pub object: String,
+    #[serde(default)]
+    pub has_more: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -5,25 +5,29 @@ use std::{
 
 use crate::{
     download::{download_url, save_b64},
-    error::OpenAIError,
+    error::{map_deserialization_error, OpenAIError},
+    tools::FunctionParameters,
     types::InputSource,
     util::{create_all_dir, create_file_part},
 };
 
 use bytes::Bytes;
+use serde::de::DeserializeOwned;
 
 use super::{
-    AudioInput, AudioResponseFormat, ChatCompletionFunctionCall, ChatCompletionFunctions,
-    ChatCompletionNamedToolChoice, ChatCompletionRequestAssistantMessage,
+    AssistantToolsFunction, AudioInput, AudioResponseFormat, ChatCompletionFunctionCall,
+    ChatCompletionFunctions, ChatCompletionNamedToolChoice, ChatCompletionRequestAssistantMessage,
     ChatCompletionRequestFunctionMessage, ChatCompletionRequestMessage,
     ChatCompletionRequestMessageContentPart, ChatCompletionRequestMessageContentPartImage,
     ChatCompletionRequestMessageContentPartText, ChatCompletionRequestSystemMessage,
     ChatCompletionRequestToolMessage, ChatCompletionRequestUserMessage,
-    ChatCompletionRequestUserMessageContent, ChatCompletionToolChoiceOption, CreateFileRequest,
+    ChatCompletionRequestUserMessageContent, ChatCompletionResponseMessage, ChatCompletionTool,
+    ChatCompletionToolChoiceOption, ChatCompletionToolType, CreateFileRequest,
     CreateImageEditRequest, CreateImageVariationRequest, CreateSpeechResponse,
     CreateTranscriptionRequest, CreateTranslationRequest, DallE2ImageSize, EmbeddingInput,
-    FileInput, FunctionName, Image, ImageInput, ImageModel, ImageSize, ImageUrl, ImagesResponse,
-    ModerationInput, Prompt, ResponseFormat, Role, Stop,
+    FileInput, FunctionCall, FunctionName, FunctionObject, Image, ImageInput, ImageModel,
+    ImageSize, ImageUrl, ImagesResponse, ModerationInput, Prompt, ResponseFormat, Role,
+    SpeechModel, Stop,
 };
 
 /// for `impl_from!(T, Enum)`, implements
@@ -117,7 +121,7 @@ impl Default for InputSource {
 }
 
 /// for `impl_input!(Struct)` where
-/// ```
+/// ```text
 /// Struct {
 ///     source: InputSource
 /// }
@@ -185,6 +189,16 @@ impl Display for DallE2ImageSize {
     }
 }
 
+impl From<DallE2ImageSize> for ImageSize {
+    fn from(value: DallE2ImageSize) -> Self {
+        match value {
+            DallE2ImageSize::S256x256 => Self::S256x256,
+            DallE2ImageSize::S512x512 => Self::S512x512,
+            DallE2ImageSize::S1024x1024 => Self::S1024x1024,
+        }
+    }
+}
+
 impl Display for ImageModel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -199,6 +213,20 @@ impl Display for ImageModel {
     }
 }
 
+impl Display for SpeechModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Tts1 => "tts-1",
+                Self::Tts1Hd => "tts-1-hd",
+                Self::Other(other) => other,
+            }
+        )
+    }
+}
+
 impl Display for ResponseFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -525,6 +553,64 @@ impl From<ChatCompletionRequestAssistantMessage> for ChatCompletionRequestMessag
     }
 }
 
+impl FunctionObject {
+    /// Function `name` with the parameters and description of `P`.
+    pub fn from_parameters<P: FunctionParameters>(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            description: P::description(),
+            parameters: Some(P::parameters_schema()),
+        }
+    }
+}
+
+impl ChatCompletionTool {
+    /// Function tool `name` with the parameters and description of `P`.
+    pub fn from_parameters<P: FunctionParameters>(name: &str) -> Self {
+        Self {
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionObject::from_parameters::<P>(name),
+        }
+    }
+}
+
+impl AssistantToolsFunction {
+    /// Function tool `name` with the parameters and description of `P`.
+    pub fn from_parameters<P: FunctionParameters>(name: &str) -> Self {
+        Self {
+            r#type: "function".into(),
+            function: FunctionObject::from_parameters::<P>(name),
+        }
+    }
+}
+
+impl FunctionCall {
+    /// Deserialize the arguments generated by the model.
+    pub fn parse_arguments<T: DeserializeOwned>(&self) -> Result<T, OpenAIError> {
+        serde_json::from_str(&self.arguments)
+            .map_err(|e| map_deserialization_error(e, self.arguments.as_bytes()))
+    }
+}
+
+impl From<ChatCompletionResponseMessage> for ChatCompletionRequestAssistantMessage {
+    #[allow(deprecated)]
+    fn from(value: ChatCompletionResponseMessage) -> Self {
+        Self {
+            content: value.content,
+            role: value.role,
+            name: None,
+            tool_calls: value.tool_calls,
+            function_call: value.function_call,
+        }
+    }
+}
+
+impl From<ChatCompletionResponseMessage> for ChatCompletionRequestMessage {
+    fn from(value: ChatCompletionResponseMessage) -> Self {
+        Self::Assistant(value.into())
+    }
+}
+
 impl From<ChatCompletionRequestFunctionMessage> for ChatCompletionRequestMessage {
     fn from(value: ChatCompletionRequestFunctionMessage) -> Self {
         Self::Function(value)
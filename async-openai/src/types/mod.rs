@@ -17,6 +17,7 @@ mod message;
 mod message_file;
 mod model;
 mod moderation;
+mod pagination;
 mod run;
 mod step;
 mod thread;
@@ -37,6 +38,7 @@ pub use message::*;
 pub use message_file::*;
 pub use model::*;
 pub use moderation::*;
+pub use pagination::*;
 pub use run::*;
 pub use step::*;
 pub use thread::*;
@@ -0,0 +1,88 @@
+use derive_builder::Builder;
+use serde::{Deserialize, Serialize};
+
+use crate::error::OpenAIError;
+
+use super::ListFineTuningJobsQuery;
+
+/// Sort order of listed objects by their `created_at` timestamp.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ListOrder {
+    Asc,
+    Desc,
+}
+
+/// Query parameters of cursor-paginated list endpoints, such as assistants, messages, runs and fine-tuning jobs.
+///
+/// List methods take any serializable `query`, usually this one, a [ListFilesQuery](super::ListFilesQuery)
+/// or a [ListFineTuningJobsQuery]. Other values, such as `[("limit", "10")]`, are sent as is,
+/// for parameters which are not supported by these queries yet.
+/// Their `_typed` variants, such as [Runs::list_typed](crate::Runs::list_typed), only take the query of their endpoint.
+#[derive(Clone, Serialize, Default, Debug, Deserialize, Builder, PartialEq)]
+#[builder(name = "ListQueryArgs")]
+#[builder(pattern = "mutable")]
+#[builder(setter(into, strip_option), default)]
+#[builder(derive(Debug))]
+#[builder(build_fn(error = "OpenAIError"))]
+pub struct ListQuery {
+    /// A limit on the number of objects to be returned. Limit can range between 1 and 100, and the default is 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u8>,
+
+    /// Sort order by the `created_at` timestamp of the objects. Not supported by fine-tuning jobs and events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<ListOrder>,
+
+    /// A cursor for use in pagination. `after` is an object ID that defines your place in the list.
+    /// For instance, if you make a list request and receive 100 objects, ending with obj_foo,
+    /// your subsequent call can include after=obj_foo in order to fetch the next page of the list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+
+    /// A cursor for use in pagination. `before` is an object ID that defines your place in the list.
+    /// For instance, if you make a list request and receive 100 objects, starting with obj_foo,
+    /// your subsequent call can include before=obj_foo in order to fetch the previous page of the list.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+}
+
+/// Query parameters of [Assistants::list](crate::Assistants::list).
+pub type ListAssistantsQuery = ListQuery;
+/// Builder of [ListAssistantsQuery].
+pub type ListAssistantsQueryArgs = ListQueryArgs;
+
+/// Query parameters of [AssistantFiles::list](crate::AssistantFiles::list).
+pub type ListAssistantFilesQuery = ListQuery;
+/// Builder of [ListAssistantFilesQuery].
+pub type ListAssistantFilesQueryArgs = ListQueryArgs;
+
+/// Query parameters of [Messages::list](crate::Messages::list).
+pub type ListMessagesQuery = ListQuery;
+/// Builder of [ListMessagesQuery].
+pub type ListMessagesQueryArgs = ListQueryArgs;
+
+/// Query parameters of [MessageFiles::list](crate::MessageFiles::list).
+pub type ListMessageFilesQuery = ListQuery;
+/// Builder of [ListMessageFilesQuery].
+pub type ListMessageFilesQueryArgs = ListQueryArgs;
+
+/// Query parameters of [Runs::list](crate::Runs::list).
+pub type ListRunsQuery = ListQuery;
+/// Builder of [ListRunsQuery].
+pub type ListRunsQueryArgs = ListQueryArgs;
+
+/// Query parameters of [Steps::list](crate::Steps::list).
+pub type ListRunStepsQuery = ListQuery;
+/// Builder of [ListRunStepsQuery].
+pub type ListRunStepsQueryArgs = ListQueryArgs;
+
+impl From<ListFineTuningJobsQuery> for ListQuery {
+    fn from(query: ListFineTuningJobsQuery) -> Self {
+        Self {
+            limit: query.limit,
+            after: query.after,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! Opt-in accounting of the usage and estimated cost of API calls, see [UsageTracker].
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    ops::AddAssign,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    catalog::{ModelCatalog, ModelPricing},
+    types::{CompletionUsage, EmbeddingUsage},
+};
+
+/// Usage aggregated over one or more API calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageTotals {
+    /// Number of API calls.
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    /// Characters of text converted to speech.
+    pub characters: u64,
+    /// Seconds of audio transcribed or translated, when reported by the API.
+    pub seconds: f64,
+    /// Images generated, edited or varied.
+    pub images: u64,
+    /// Estimated cost in USD, from the prices of the [ModelCatalog] of the tracker.
+    /// `None` when none of the usage has a price in the catalog, or is known, see [UsageTracker::record_request].
+    pub cost: Option<f64>,
+}
+
+impl AddAssign for UsageTotals {
+    fn add_assign(&mut self, other: Self) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.characters += other.characters;
+        self.seconds += other.seconds;
+        self.images += other.images;
+        self.cost = match (self.cost, other.cost) {
+            (Some(cost), Some(other)) => Some(cost + other),
+            (cost, None) | (None, cost) => cost,
+        };
+    }
+}
+
+impl From<&CompletionUsage> for UsageTotals {
+    fn from(usage: &CompletionUsage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens as u64,
+            completion_tokens: usage.completion_tokens as u64,
+            total_tokens: usage.total_tokens as u64,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&EmbeddingUsage> for UsageTotals {
+    fn from(usage: &EmbeddingUsage) -> Self {
+        Self {
+            prompt_tokens: usage.prompt_tokens as u64,
+            total_tokens: usage.total_tokens as u64,
+            ..Default::default()
+        }
+    }
+}
+
+impl UsageTotals {
+    /// Estimated cost of this usage with `pricing`.
+    /// Images are priced by their size and quality, which are not part of the usage, so their cost is `None`.
+    fn estimate_cost(&self, pricing: &ModelPricing) -> Option<f64> {
+        match pricing {
+            ModelPricing::Tokens { input, output } => Some(
+                (self.prompt_tokens as f64 * input + self.completion_tokens as f64 * output)
+                    / 1_000_000.0,
+            ),
+            ModelPricing::Image { .. } => None,
+            ModelPricing::Characters { per_million } => {
+                Some(self.characters as f64 * per_million / 1_000_000.0)
+            }
+            ModelPricing::Minutes { per_minute } => Some(self.seconds / 60.0 * per_minute),
+        }
+    }
+}
+
+/// What usage is recorded against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UsageKey {
+    /// Model of the request.
+    pub model: String,
+    /// API group of the request: `chat`, `completions`, `embeddings`, `audio` or `images`.
+    pub api: String,
+    /// `user` field of the request.
+    pub user: Option<String>,
+    /// Tag of the tracker which recorded the usage, see [UsageTracker::tagged].
+    pub tag: Option<String>,
+}
+
+/// Records the usage of API calls made by a [crate::Client], set with [crate::Client::with_usage_tracker].
+///
+/// Token usage is recorded from chat, completion and embedding responses, characters for text to speech,
+/// seconds of audio for transcriptions and translations in the `verbose_json` format, and the number of generated images.
+/// Streamed chat completions report their usage when [crate::types::ChatCompletionStreamOptions::include_usage] is set.
+///
+/// Clones and [tagged](UsageTracker::tagged) trackers share the same records,
+/// so a client can be cloned per feature with a different tag:
+///
+/// ```
+/// use async_openai::{usage::UsageTracker, Client};
+///
+/// let tracker = UsageTracker::new();
+/// let client = Client::new().with_usage_tracker(tracker.clone());
+/// let search_client = client.clone().with_usage_tracker(tracker.tagged("search"));
+///
+/// // ... make API calls with both clients
+///
+/// for (tag, usage) in tracker.by_tag() {
+///     let cost = usage.cost.unwrap_or_default();
+///     println!("{tag:?}: {} requests, ${cost:.2}", usage.requests);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct UsageTracker {
+    records: Arc<Mutex<HashMap<UsageKey, UsageTotals>>>,
+    catalog: Arc<ModelCatalog>,
+    tag: Option<String>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self {
+            records: Default::default(),
+            catalog: Arc::new(ModelCatalog::default()),
+            tag: None,
+        }
+    }
+}
+
+impl UsageTracker {
+    /// Tracker estimating costs with the built-in [ModelCatalog].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Estimate costs with the prices of `catalog`.
+    pub fn with_catalog(mut self, catalog: ModelCatalog) -> Self {
+        self.catalog = Arc::new(catalog);
+        self
+    }
+
+    /// Catalog of the prices costs are estimated with.
+    pub fn catalog(&self) -> &ModelCatalog {
+        &self.catalog
+    }
+
+    /// Tracker sharing the records of this one, recording usage under `tag`.
+    pub fn tagged<S: Into<String>>(&self, tag: S) -> Self {
+        Self {
+            tag: Some(tag.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Record `usage` of a request to `model` through the `api` group.
+    /// Its cost is estimated from the catalog, unless already set.
+    pub fn record(&self, api: &str, model: &str, user: Option<&str>, mut usage: UsageTotals) {
+        if usage.cost.is_none() {
+            usage.cost = self
+                .catalog
+                .get(model)
+                .and_then(|info| info.pricing.as_ref())
+                .and_then(|pricing| usage.estimate_cost(pricing));
+        }
+        self.add(api, model, user, usage);
+    }
+
+    /// Record a request to `model` whose usage is unknown, such as a stream without
+    /// [include_usage](crate::types::ChatCompletionStreamOptions::include_usage), which has no cost then.
+    pub fn record_request(&self, api: &str, model: &str, user: Option<&str>) {
+        let usage = UsageTotals {
+            requests: 1,
+            ..Default::default()
+        };
+        self.add(api, model, user, usage);
+    }
+
+    fn add(&self, api: &str, model: &str, user: Option<&str>, usage: UsageTotals) {
+        let key = UsageKey {
+            model: model.to_string(),
+            api: api.to_string(),
+            user: user.map(str::to_string),
+            tag: self.tag.clone(),
+        };
+        *self.records.lock().unwrap().entry(key).or_default() += usage;
+    }
+
+    /// Usage by every combination of model, API group, user and tag recorded so far.
+    pub fn entries(&self) -> Vec<(UsageKey, UsageTotals)> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, usage)| (key.clone(), *usage))
+            .collect()
+    }
+
+    /// Usage of all API calls.
+    pub fn total(&self) -> UsageTotals {
+        let mut total = UsageTotals::default();
+        for usage in self.records.lock().unwrap().values() {
+            total += *usage;
+        }
+        total
+    }
+
+    pub fn by_model(&self) -> HashMap<String, UsageTotals> {
+        self.group_by(|key| key.model.clone())
+    }
+
+    pub fn by_api(&self) -> HashMap<String, UsageTotals> {
+        self.group_by(|key| key.api.clone())
+    }
+
+    pub fn by_user(&self) -> HashMap<Option<String>, UsageTotals> {
+        self.group_by(|key| key.user.clone())
+    }
+
+    pub fn by_tag(&self) -> HashMap<Option<String>, UsageTotals> {
+        self.group_by(|key| key.tag.clone())
+    }
+
+    /// Clear the records shared by all clones of this tracker.
+    pub fn reset(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    fn group_by<K: Eq + Hash>(&self, group: impl Fn(&UsageKey) -> K) -> HashMap<K, UsageTotals> {
+        let mut groups: HashMap<K, UsageTotals> = HashMap::new();
+        for (key, usage) in self.records.lock().unwrap().iter() {
+            *groups.entry(group(key)).or_default() += *usage;
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use serde_json::json;
+
+    use super::{UsageTotals, UsageTracker};
+    use crate::{
+        testing::{MockResponse, MockServer},
+        types::{
+            AudioInput, ChatCompletionRequestUserMessageArgs, CompletionUsage,
+            CreateChatCompletionRequestArgs, CreateImageRequestArgs,
+            CreateTranscriptionRequestArgs, ImageModel, ImageQuality, ImageSize,
+        },
+    };
+
+    #[test]
+    fn test_usage_tracker() {
+        let tracker = UsageTracker::new();
+        let search = tracker.tagged("search");
+
+        let usage = CompletionUsage {
+            prompt_tokens: 1000,
+            completion_tokens: 500,
+            total_tokens: 1500,
+        };
+        tracker.record(
+            "chat",
+            "gpt-4-0613",
+            Some("alice"),
+            UsageTotals {
+                requests: 1,
+                ..(&usage).into()
+            },
+        );
+        search.record(
+            "chat",
+            "gpt-4-0613",
+            None,
+            UsageTotals {
+                requests: 1,
+                ..(&usage).into()
+            },
+        );
+        search.record(
+            "audio",
+            "tts-1",
+            None,
+            UsageTotals {
+                requests: 1,
+                characters: 2000,
+                ..Default::default()
+            },
+        );
+        search.record(
+            "images",
+            "unknown-model",
+            None,
+            UsageTotals {
+                requests: 1,
+                images: 2,
+                ..Default::default()
+            },
+        );
+
+        let total = tracker.total();
+        assert_eq!(total.requests, 4);
+        assert_eq!(total.total_tokens, 3000);
+        assert!((total.cost.unwrap() - 0.15).abs() < 1e-9);
+
+        let by_model = tracker.by_model();
+        assert!((by_model["gpt-4-0613"].cost.unwrap() - 0.12).abs() < 1e-9);
+        assert_eq!(by_model["unknown-model"].cost, None);
+        assert_eq!(tracker.by_api()["chat"].requests, 2);
+        assert_eq!(tracker.by_user()[&Some("alice".into())].requests, 1);
+        assert_eq!(tracker.by_tag()[&Some("search".into())].requests, 3);
+
+        search.reset();
+        assert_eq!(tracker.total(), UsageTotals::default());
+    }
+
+    #[tokio::test]
+    async fn test_image_cost() {
+        let server = MockServer::start().await;
+        let image = json!({"url": "https://example.com/image.png"});
+        server.mock(
+            "POST",
+            "/images/generations",
+            MockResponse::json(&json!({"created": 0, "data": [image]})),
+        );
+        let tracker = UsageTracker::new();
+        let client = server.client().with_usage_tracker(tracker.clone());
+
+        let request = CreateImageRequestArgs::default()
+            .prompt("A cat")
+            .model(ImageModel::DallE3)
+            .size(ImageSize::S1792x1024)
+            .quality(ImageQuality::HD)
+            .build()
+            .unwrap();
+        client.images().create(request).await.unwrap();
+
+        let request = CreateImageRequestArgs::default()
+            .prompt("A cat")
+            .model(ImageModel::DallE3)
+            .build()
+            .unwrap();
+        client.images().create(request).await.unwrap();
+
+        let usage = tracker.by_model()["dall-e-3"];
+        assert_eq!(usage.images, 2);
+        assert!((usage.cost.unwrap() - 0.16).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_usage_cost() {
+        let server = MockServer::start().await;
+        let chunk = json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-3.5-turbo",
+            "choices": [{"index": 0, "delta": {"content": "Hi"}, "finish_reason": null}]
+        });
+        server
+            .mock("POST", "/chat/completions", MockResponse::sse([chunk]))
+            .mock(
+                "POST",
+                "/audio/transcriptions",
+                MockResponse::json(&json!({"text": "Hi"})),
+            );
+        let tracker = UsageTracker::new();
+        let client = server.client().with_usage_tracker(tracker.clone());
+
+        // Stream without include_usage
+        let request = CreateChatCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .messages([ChatCompletionRequestUserMessageArgs::default()
+                .content("Hello!")
+                .build()
+                .unwrap()
+                .into()])
+            .build()
+            .unwrap();
+        let stream = client.chat().create_stream(request).await.unwrap();
+        stream.for_each(|_| async {}).await;
+
+        // Transcription without duration, which is only in verbose_json
+        let request = CreateTranscriptionRequestArgs::default()
+            .file(AudioInput::from_vec_u8("hi.mp3".into(), vec![0; 16]))
+            .model("whisper-1")
+            .build()
+            .unwrap();
+        client.audio().transcribe(request).await.unwrap();
+
+        let by_model = tracker.by_model();
+        assert_eq!(by_model["gpt-3.5-turbo"].requests, 1);
+        assert_eq!(by_model["gpt-3.5-turbo"].cost, None);
+        assert_eq!(by_model["whisper-1"].requests, 1);
+        assert_eq!(by_model["whisper-1"].cost, None);
+    }
+}
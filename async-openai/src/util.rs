@@ -1,5 +1,7 @@
 use std::path::Path;
+use std::time::Duration;
 
+use reqwest::header::HeaderMap;
 use reqwest::Body;
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
@@ -7,6 +9,12 @@ use tokio_util::codec::{BytesCodec, FramedRead};
 use crate::error::OpenAIError;
 use crate::types::InputSource;
 
+/// Tokens of the chat format around every message, counted by the tokenizer and estimated by the rate limiter.
+pub(crate) const TOKENS_PER_MESSAGE: usize = 3;
+
+/// Lowest token cost of an image part, for `low` detail images.
+pub(crate) const TOKENS_PER_IMAGE: usize = 85;
+
 pub(crate) async fn file_stream_body(source: InputSource) -> Result<Body, OpenAIError> {
     let body = match source {
         InputSource::Path{ path } => {
@@ -64,3 +72,131 @@ pub(crate) fn create_all_dir<P: AsRef<Path>>(dir: P) -> Result<(), OpenAIError>
 
     Ok(())
 }
+
+/// Parses durations in the format of `x-ratelimit-reset-*` headers, for example `6m0s`, `20ms`, `1.5s` or `1h2m3s`.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    let mut total = 0f64;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "h" => number * 3600.0,
+            "m" => number * 60.0,
+            "s" => number,
+            "ms" => number / 1_000.0,
+            "us" | "µs" => number / 1_000_000.0,
+            "ns" => number / 1_000_000_000.0,
+            _ => return None,
+        };
+        total += seconds;
+        rest = &rest[unit_len..];
+    }
+
+    Duration::try_from_secs_f64(total).ok()
+}
+
+/// Delay requested by the server before a rate limited request can be retried.
+///
+/// `retry-after-ms` and `Retry-After` (in seconds) take precedence, otherwise the reset time
+/// of whichever `x-ratelimit-*` budget is exhausted is used.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+    // Values which don't fit a duration, such as `NaN`, `inf` or `1e30`, are treated as absent
+    let seconds = |value: &str, scale: f64| {
+        let number = value.trim().parse::<f64>().ok().filter(|n| !n.is_nan())?;
+        Duration::try_from_secs_f64(number.max(0.0) / scale).ok()
+    };
+
+    if let Some(delay) = header("retry-after-ms").and_then(|v| seconds(v, 1_000.0)) {
+        return Some(delay);
+    }
+
+    if let Some(delay) = header("retry-after").and_then(|v| seconds(v, 1.0)) {
+        return Some(delay);
+    }
+
+    let exhausted = |name: &str| header(name).and_then(|v| v.trim().parse::<u64>().ok()) == Some(0);
+    let reset_requests = header("x-ratelimit-reset-requests").and_then(parse_duration);
+    let reset_tokens = header("x-ratelimit-reset-tokens").and_then(parse_duration);
+
+    match (
+        exhausted("x-ratelimit-remaining-requests"),
+        exhausted("x-ratelimit-remaining-tokens"),
+    ) {
+        (true, false) => reset_requests,
+        (false, true) => reset_tokens,
+        // Both or neither budget is known to be exhausted: wait for the later reset.
+        _ => reset_requests.max(reset_tokens),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::header::HeaderMap;
+
+    use super::{parse_duration, retry_after};
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(parse_duration("20ms"), Some(Duration::from_millis(20)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("1h2m3s"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("inf"), None);
+        assert_eq!(parse_duration("1e30s"), None);
+        assert_eq!(parse_duration("99999999999999999999h"), None);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining-requests", "10".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-tokens", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "6m0s".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "20ms".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(20)));
+
+        headers.remove("x-ratelimit-remaining-tokens");
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(360)));
+
+        headers.insert("retry-after", "2".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+
+        headers.insert("retry-after-ms", "150".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(150)));
+
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+
+        // Values which overflow a duration are ignored instead of panicking
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", "1e30".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("retry-after-ms", "inf".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(
+            "x-ratelimit-reset-requests",
+            "99999999999999999999h".parse().unwrap(),
+        );
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("retry-after", "NaN".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+}
@@ -0,0 +1,502 @@
+//! Client side checks of the documented constraints of requests, see [Validate].
+use std::fmt::Display;
+
+use crate::{
+    catalog::ModelCatalog,
+    error::OpenAIError,
+    types::{
+        ChatCompletionToolChoiceOption, CreateAssistantRequest, CreateChatCompletionRequest,
+        CreateCompletionRequest, CreateEmbeddingRequest, CreateFineTuningJobRequest,
+        CreateImageEditRequest, CreateImageRequest, CreateImageVariationRequest,
+        CreateMessageRequest, CreateSpeechRequest, CreateTranscriptionRequest,
+        CreateTranslationRequest, EmbeddingInput, ModifyAssistantRequest, NEpochs, Stop,
+    },
+};
+
+/// Requests which can be checked before they are sent.
+///
+/// API groups validate requests before sending them when enabled with [crate::Client::with_request_validation].
+/// [Validate::validate] checks constraints which hold for every model, and [Validate::validate_model]
+/// the rules of the model of the request from a [ModelCatalog].
+/// Violations are reported as [OpenAIError::InvalidArgument] starting with the path of the field, like `messages[0].name`.
+///
+/// ```
+/// use async_openai::{
+///     catalog::ModelCatalog, types::CreateImageRequestArgs, types::ImageModel, validate::Validate,
+/// };
+///
+/// let request = CreateImageRequestArgs::default()
+///     .prompt("A cute baby sea otter")
+///     .model(ImageModel::DallE3)
+///     .n(2)
+///     .build()
+///     .unwrap();
+///
+/// assert!(request.validate().is_ok());
+/// let error = request.validate_model(&ModelCatalog::default()).unwrap_err();
+/// assert_eq!(error.to_string(), "invalid args: dall-e-3 supports at most 1 images per request, got 2");
+/// ```
+pub trait Validate {
+    fn validate(&self) -> Result<(), OpenAIError>;
+
+    /// Check the request against the limits and features of its model in `catalog`.
+    /// Models which are not in the catalog are not checked.
+    fn validate_model(&self, _catalog: &ModelCatalog) -> Result<(), OpenAIError> {
+        Ok(())
+    }
+}
+
+fn invalid(path: impl Display, message: impl Display) -> OpenAIError {
+    OpenAIError::InvalidArgument(format!("{path}: {message}"))
+}
+
+fn check_range<T: PartialOrd + Display + Copy>(
+    path: &str,
+    value: Option<T>,
+    min: T,
+    max: T,
+) -> Result<(), OpenAIError> {
+    match value {
+        Some(value) if value < min || value > max => Err(invalid(
+            path,
+            format!("must be between {min} and {max}, got {value}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_length(
+    path: &str,
+    value: Option<&str>,
+    min: usize,
+    max: usize,
+) -> Result<(), OpenAIError> {
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let length = value.chars().count();
+    if length < min {
+        Err(invalid(path, "must not be empty"))
+    } else if length > max {
+        Err(invalid(
+            path,
+            format!("must be at most {max} characters, got {length}"),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_items<T>(
+    path: &str,
+    items: Option<&[T]>,
+    min: usize,
+    max: usize,
+) -> Result<(), OpenAIError> {
+    match items {
+        Some(items) if items.is_empty() && min > 0 => Err(invalid(path, "must not be empty")),
+        Some(items) if items.len() < min => Err(invalid(
+            path,
+            format!("must have at least {min} items, got {}", items.len()),
+        )),
+        Some(items) if items.len() > max => Err(invalid(
+            path,
+            format!("must have at most {max} items, got {}", items.len()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_stop(stop: &Option<Stop>) -> Result<(), OpenAIError> {
+    match stop {
+        Some(Stop::StringArray(stop)) => check_items("stop", Some(stop), 1, 4),
+        _ => Ok(()),
+    }
+}
+
+/// Function names must be a-z, A-Z, 0-9, underscores and dashes, with a maximum length of 64.
+fn check_function_name(path: &str, name: &str) -> Result<(), OpenAIError> {
+    check_length(path, Some(name), 1, 64)?;
+    if let Some(character) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '-'))
+    {
+        return Err(invalid(
+            path,
+            format!("must only contain a-z, A-Z, 0-9, underscores and dashes, got {character:?}"),
+        ));
+    }
+    Ok(())
+}
+
+impl Validate for CreateChatCompletionRequest {
+    #[allow(deprecated)]
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_items("messages", Some(&self.messages), 1, usize::MAX)?;
+        check_range("frequency_penalty", self.frequency_penalty, -2.0, 2.0)?;
+        check_range("presence_penalty", self.presence_penalty, -2.0, 2.0)?;
+        check_range("temperature", self.temperature, 0.0, 2.0)?;
+        check_range("top_p", self.top_p, 0.0, 1.0)?;
+        check_range("n", self.n, 1, 128)?;
+        check_range("top_logprobs", self.top_logprobs, 0, 20)?;
+        if self.top_logprobs.is_some() && self.logprobs != Some(true) {
+            return Err(invalid("top_logprobs", "requires logprobs to be true"));
+        }
+        if self.stream_options.is_some() && self.stream != Some(true) {
+            return Err(invalid("stream_options", "requires stream to be true"));
+        }
+        check_stop(&self.stop)?;
+
+        for (token, bias) in self.logit_bias.iter().flatten() {
+            let path = format!("logit_bias[{token:?}]");
+            match bias.as_f64() {
+                Some(bias) => check_range(&path, Some(bias), -100.0, 100.0)?,
+                None => return Err(invalid(path, "must be a number")),
+            }
+        }
+
+        for (index, tool) in self.tools.iter().flatten().enumerate() {
+            check_function_name(
+                &format!("tools[{index}].function.name"),
+                &tool.function.name,
+            )?;
+        }
+        for (index, function) in self.functions.iter().flatten().enumerate() {
+            check_function_name(&format!("functions[{index}].name"), &function.name)?;
+        }
+        if let Some(ChatCompletionToolChoiceOption::Named(choice)) = &self.tool_choice {
+            let known = self
+                .tools
+                .iter()
+                .flatten()
+                .any(|tool| tool.function.name == choice.function.name);
+            if !known {
+                return Err(invalid(
+                    "tool_choice.function.name",
+                    format!("{} is not one of the tools", choice.function.name),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_model(&self, catalog: &ModelCatalog) -> Result<(), OpenAIError> {
+        catalog.check_chat_request(self)
+    }
+}
+
+impl Validate for CreateCompletionRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_range("temperature", self.temperature, 0.0, 2.0)?;
+        check_range("top_p", self.top_p, 0.0, 1.0)?;
+        check_range("n", self.n, 1, 128)?;
+        check_range("logprobs", self.logprobs, 0, 5)?;
+        check_range("presence_penalty", self.presence_penalty, -2.0, 2.0)?;
+        check_range("frequency_penalty", self.frequency_penalty, -2.0, 2.0)?;
+        check_range("best_of", self.best_of, 0, 20)?;
+        if let (Some(best_of), Some(n)) = (self.best_of, self.n) {
+            if best_of < n {
+                return Err(invalid(
+                    "best_of",
+                    format!("must be greater than or equal to n, got {best_of} < {n}"),
+                ));
+            }
+        }
+        check_stop(&self.stop)
+    }
+}
+
+impl Validate for CreateEmbeddingRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        match &self.input {
+            EmbeddingInput::String(input) => check_length("input", Some(input), 1, usize::MAX)?,
+            EmbeddingInput::StringArray(inputs) => {
+                check_items("input", Some(inputs), 1, 2048)?;
+                for (index, input) in inputs.iter().enumerate() {
+                    check_length(&format!("input[{index}]"), Some(input), 1, usize::MAX)?;
+                }
+            }
+            EmbeddingInput::IntegerArray(tokens) => {
+                check_items("input", Some(tokens), 1, usize::MAX)?
+            }
+            EmbeddingInput::ArrayOfIntegerArray(inputs) => {
+                check_items("input", Some(inputs), 1, 2048)?;
+                for (index, tokens) in inputs.iter().enumerate() {
+                    check_items(&format!("input[{index}]"), Some(tokens), 1, usize::MAX)?;
+                }
+            }
+        }
+
+        check_range("dimensions", self.dimensions, 1, u32::MAX)
+    }
+
+    fn validate_model(&self, catalog: &ModelCatalog) -> Result<(), OpenAIError> {
+        catalog.check_embedding_request(self)
+    }
+}
+
+impl Validate for CreateImageRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_length("prompt", Some(&self.prompt), 1, usize::MAX)?;
+        check_range("n", self.n, 1, 10)
+    }
+
+    fn validate_model(&self, catalog: &ModelCatalog) -> Result<(), OpenAIError> {
+        catalog.check_image_request(self)
+    }
+}
+
+impl Validate for CreateImageEditRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_length("prompt", Some(&self.prompt), 1, usize::MAX)?;
+        check_range("n", self.n, 1, 10)
+    }
+
+    fn validate_model(&self, catalog: &ModelCatalog) -> Result<(), OpenAIError> {
+        catalog.check_image_edit_request(self)
+    }
+}
+
+impl Validate for CreateImageVariationRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_range("n", self.n, 1, 10)
+    }
+
+    fn validate_model(&self, catalog: &ModelCatalog) -> Result<(), OpenAIError> {
+        catalog.check_image_variation_request(self)
+    }
+}
+
+impl Validate for CreateSpeechRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_length("input", Some(&self.input), 1, 4096)?;
+        check_range("speed", self.speed, 0.25, 4.0)
+    }
+}
+
+impl Validate for CreateTranscriptionRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_range("temperature", self.temperature, 0.0, 1.0)
+    }
+}
+
+impl Validate for CreateTranslationRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_range("temperature", self.temperature, 0.0, 1.0)
+    }
+}
+
+impl Validate for CreateFineTuningJobRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_length("suffix", self.suffix.as_deref(), 1, 40)?;
+        if let Some(NEpochs::NEpochs(n_epochs)) = self
+            .hyperparameters
+            .as_ref()
+            .map(|hyperparameters| &hyperparameters.n_epochs)
+        {
+            check_range("hyperparameters.n_epochs", Some(*n_epochs), 1, 50)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_assistant<T>(
+    name: Option<&str>,
+    description: Option<&str>,
+    instructions: Option<&str>,
+    tools: Option<&[T]>,
+    file_ids: Option<&[String]>,
+) -> Result<(), OpenAIError> {
+    check_length("name", name, 0, 256)?;
+    check_length("description", description, 0, 512)?;
+    check_length("instructions", instructions, 0, 32768)?;
+    check_items("tools", tools, 0, 128)?;
+    check_items("file_ids", file_ids, 0, 20)
+}
+
+impl Validate for CreateAssistantRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_assistant(
+            self.name.as_deref(),
+            self.description.as_deref(),
+            self.instructions.as_deref(),
+            self.tools.as_deref(),
+            self.file_ids.as_deref(),
+        )
+    }
+}
+
+impl Validate for ModifyAssistantRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_assistant(
+            self.name.as_deref(),
+            self.description.as_deref(),
+            self.instructions.as_deref(),
+            self.tools.as_deref(),
+            self.file_ids.as_deref(),
+        )
+    }
+}
+
+impl Validate for CreateMessageRequest {
+    fn validate(&self) -> Result<(), OpenAIError> {
+        check_items("file_ids", self.file_ids.as_deref(), 0, 10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Validate;
+    use crate::{
+        catalog::ModelCatalog,
+        types::{
+            ChatCompletionRequestUserMessageArgs, ChatCompletionToolArgs,
+            CreateChatCompletionRequestArgs, CreateCompletionRequestArgs,
+            CreateEmbeddingRequestArgs, CreateFineTuningJobRequestArgs, CreateImageRequestArgs,
+            FunctionObjectArgs, ImageModel, Stop,
+        },
+    };
+
+    fn error<V: Validate>(request: V) -> String {
+        request.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_validate_requests() {
+        let chat = || {
+            let mut request = CreateChatCompletionRequestArgs::default();
+            request.model("gpt-3.5-turbo").messages([
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content("Hello!")
+                    .build()
+                    .unwrap()
+                    .into(),
+            ]);
+            request
+        };
+        assert!(chat().build().unwrap().validate().is_ok());
+        assert_eq!(
+            error(chat().messages([]).build().unwrap()),
+            "invalid args: messages: must not be empty"
+        );
+        assert_eq!(
+            error(chat().frequency_penalty(2.5).build().unwrap()),
+            "invalid args: frequency_penalty: must be between -2 and 2, got 2.5"
+        );
+        assert_eq!(
+            error(chat().n(0).build().unwrap()),
+            "invalid args: n: must be between 1 and 128, got 0"
+        );
+        assert_eq!(
+            error(
+                chat()
+                    .stop(Stop::StringArray(vec!["a".into(); 5]))
+                    .build()
+                    .unwrap()
+            ),
+            "invalid args: stop: must have at most 4 items, got 5"
+        );
+        assert_eq!(
+            error(chat().top_logprobs(2).build().unwrap()),
+            "invalid args: top_logprobs: requires logprobs to be true"
+        );
+        assert!(chat()
+            .logprobs(true)
+            .top_logprobs(20)
+            .build()
+            .unwrap()
+            .validate()
+            .is_ok());
+        assert_eq!(
+            error(chat().logprobs(true).top_logprobs(21).build().unwrap()),
+            "invalid args: top_logprobs: must be between 0 and 20, got 21"
+        );
+        let tool = ChatCompletionToolArgs::default()
+            .function(
+                FunctionObjectArgs::default()
+                    .name("get weather")
+                    .build()
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            error(chat().tools(vec![tool]).build().unwrap()),
+            "invalid args: tools[0].function.name: must only contain a-z, A-Z, 0-9, underscores and dashes, got ' '"
+        );
+
+        let completion = CreateCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo-instruct")
+            .n(3)
+            .best_of(2)
+            .build()
+            .unwrap();
+        assert_eq!(
+            error(completion),
+            "invalid args: best_of: must be greater than or equal to n, got 2 < 3"
+        );
+
+        let embedding = CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-3-small")
+            .input(vec!["a", ""])
+            .build()
+            .unwrap();
+        assert_eq!(
+            error(embedding),
+            "invalid args: input[1]: must not be empty"
+        );
+
+        // Model rules come from the catalog
+        let embedding = CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-ada-002")
+            .input("a")
+            .dimensions(256_u32)
+            .build()
+            .unwrap();
+        assert!(embedding.validate().is_ok());
+        assert_eq!(
+            embedding
+                .validate_model(&ModelCatalog::default())
+                .unwrap_err()
+                .to_string(),
+            "invalid args: text-embedding-ada-002 doesn't support 256 dimensions"
+        );
+        assert!(embedding.validate_model(&ModelCatalog::empty()).is_ok());
+
+        let image = || {
+            let mut request = CreateImageRequestArgs::default();
+            request.prompt("A cute baby sea otter");
+            request
+        };
+        assert!(image().n(4).build().unwrap().validate().is_ok());
+        assert_eq!(
+            error(image().n(11).build().unwrap()),
+            "invalid args: n: must be between 1 and 10, got 11"
+        );
+        assert_eq!(
+            error(image().prompt("").build().unwrap()),
+            "invalid args: prompt: must not be empty"
+        );
+        let dall_e_3 = image().model(ImageModel::DallE3).n(2).build().unwrap();
+        assert!(dall_e_3.validate().is_ok());
+        assert_eq!(
+            dall_e_3
+                .validate_model(&ModelCatalog::default())
+                .unwrap_err()
+                .to_string(),
+            "invalid args: dall-e-3 supports at most 1 images per request, got 2"
+        );
+
+        let fine_tuning = CreateFineTuningJobRequestArgs::default()
+            .model("gpt-3.5-turbo")
+            .training_file("file-abc123")
+            .suffix("a".repeat(41))
+            .build()
+            .unwrap();
+        assert_eq!(
+            error(fine_tuning),
+            "invalid args: suffix: must be at most 40 characters, got 41"
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use futures::future::{BoxFuture, FutureExt};
 use futures::StreamExt;
+use serde_json::json;
 
+use async_openai::testing::{MockResponse, MockServer};
 use async_openai::types::{CompletionResponseStream, CreateCompletionRequestArgs};
-use async_openai::Client;
 
 #[tokio::test]
 async fn boxed_future_test() {
@@ -24,7 +25,22 @@ async fn boxed_future_test() {
         .boxed()
     }
 
-    let client = Client::new();
+    let server = MockServer::start().await;
+    let chunk = |text: &str| {
+        json!({
+            "id": "cmpl-1",
+            "object": "text_completion",
+            "created": 0,
+            "model": "text-babbage-001",
+            "choices": [{"text": text, "index": 0, "logprobs": null, "finish_reason": null}]
+        })
+    };
+    server.mock(
+        "POST",
+        "/completions",
+        MockResponse::sse([chunk("\n"), chunk(" Yes"), chunk(".")]),
+    );
+    let client = server.client();
 
     let request = CreateCompletionRequestArgs::default()
         .model("text-babbage-001")
@@ -1,5 +1,7 @@
 //! This test is primarily to make sure that macros_rules for From traits are correct.
-use async_openai::types::Prompt;
+use async_openai::testing::{MockResponse, MockServer};
+use async_openai::types::{CreateCompletionRequestArgs, Prompt};
+use serde_json::{json, Value};
 
 fn prompt_input<T>(input: T) -> Prompt
 where
@@ -45,3 +47,53 @@ fn create_prompt_input() {
     let _ = prompt_input(&prompt);
     let _ = prompt_input(prompt);
 }
+
+#[tokio::test]
+async fn send_prompt_input() {
+    let server = MockServer::start().await;
+    server.mock(
+        "POST",
+        "/completions",
+        MockResponse::json(&json!({
+            "id": "cmpl-1",
+            "object": "text_completion",
+            "created": 0,
+            "model": "gpt-3.5-turbo-instruct",
+            "choices": [
+                {"text": "1", "index": 0, "logprobs": null, "finish_reason": "stop"},
+                {"text": "2", "index": 1, "logprobs": null, "finish_reason": "stop"}
+            ]
+        })),
+    );
+    let client = server.client();
+
+    let prompts = [
+        prompt_input("This is &str prompt"),
+        prompt_input(vec![
+            "First string".to_string(),
+            "Second string".to_string(),
+        ]),
+    ];
+    for prompt in prompts {
+        let request = CreateCompletionRequestArgs::default()
+            .model("gpt-3.5-turbo-instruct")
+            .prompt(prompt)
+            .build()
+            .unwrap();
+        let response = client.completions().create(request).await.unwrap();
+        assert_eq!(response.choices.len(), 2);
+    }
+
+    let sent: Vec<Value> = server
+        .requests()
+        .iter()
+        .map(|request| request.json::<Value>().unwrap()["prompt"].clone())
+        .collect();
+    assert_eq!(
+        sent,
+        [
+            json!("This is &str prompt"),
+            json!(["First string", "Second string"])
+        ]
+    );
+}
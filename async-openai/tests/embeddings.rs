@@ -1,5 +1,7 @@
 //! This test is primarily to make sure that macros_rules for From traits are correct.
-use async_openai::types::EmbeddingInput;
+use async_openai::testing::{MockResponse, MockServer};
+use async_openai::types::{CreateEmbeddingRequestArgs, EmbeddingInput};
+use serde_json::{json, Value};
 
 fn embedding_input<T>(input: T) -> EmbeddingInput
 where
@@ -44,3 +46,40 @@ fn create_embedding_input() {
     let _ = embedding_input(&input);
     let _ = embedding_input(input);
 }
+
+#[tokio::test]
+async fn send_embedding_input() {
+    let server = MockServer::start().await;
+    server.mock(
+        "POST",
+        "/embeddings",
+        MockResponse::json(&json!({
+            "object": "list",
+            "model": "text-embedding-ada-002",
+            "data": [{"index": 0, "object": "embedding", "embedding": [0.1, 0.2]}],
+            "usage": {"prompt_tokens": 3, "total_tokens": 3}
+        })),
+    );
+    let client = server.client();
+
+    let inputs = [
+        embedding_input([1, 2, 3]),
+        embedding_input(vec![vec![1, 2, 3], vec![4, 5, 6, 7]]),
+    ];
+    for input in inputs {
+        let request = CreateEmbeddingRequestArgs::default()
+            .model("text-embedding-ada-002")
+            .input(input)
+            .build()
+            .unwrap();
+        let response = client.embeddings().create(request).await.unwrap();
+        assert_eq!(response.data[0].embedding, vec![0.1, 0.2]);
+    }
+
+    let sent: Vec<Value> = server
+        .requests()
+        .iter()
+        .map(|request| request.json::<Value>().unwrap()["input"].clone())
+        .collect();
+    assert_eq!(sent, [json!([1, 2, 3]), json!([[1, 2, 3], [4, 5, 6, 7]])]);
+}
@@ -1,21 +1,27 @@
-use async_openai::types::CreateTranslationRequestArgs;
-use async_openai::{types::CreateTranscriptionRequestArgs, Client};
+use async_openai::testing::{MockResponse, MockServer};
+use async_openai::types::CreateTranscriptionRequestArgs;
+use async_openai::types::{AudioInput, AudioResponseFormat, CreateTranslationRequestArgs};
+use bytes::Bytes;
+use serde_json::json;
 use tokio_test::assert_err;
 
 #[tokio::test]
 async fn transcribe_test() {
-    let client = Client::new();
+    let server = MockServer::start().await;
+    let client = server.client();
 
     let request = CreateTranscriptionRequestArgs::default().build().unwrap();
 
     let response = client.audio().transcribe(request).await;
 
     assert_err!(response); // FileReadError("cannot extract file name from ")
+    assert!(server.requests().is_empty());
 }
 
 #[tokio::test]
 async fn transcribe_sendable_test() {
-    let client = Client::new();
+    let server = MockServer::start().await;
+    let client = server.client();
 
     // https://github.com/64bit/async-openai/issues/140
     let transcribe = tokio::spawn(async move {
@@ -29,20 +35,58 @@ async fn transcribe_sendable_test() {
     assert_err!(response); // FileReadError("cannot extract file name from ")
 }
 
+#[tokio::test]
+async fn transcribe_multipart_test() {
+    let server = MockServer::start().await;
+    server.mock(
+        "POST",
+        "/audio/transcriptions",
+        MockResponse::json(&json!({"text": "Hello world"})),
+    );
+    let client = server.client();
+
+    let request = CreateTranscriptionRequestArgs::default()
+        .file(AudioInput::from_bytes(
+            "hello.mp3".into(),
+            Bytes::from_static(b"ID3 audio"),
+        ))
+        .model("whisper-1")
+        .language("en")
+        .response_format(AudioResponseFormat::Json)
+        .build()
+        .unwrap();
+
+    let response = client.audio().transcribe(request).await.unwrap();
+    assert_eq!(response.text, "Hello world");
+
+    let requests = server.requests();
+    assert_eq!(requests[0].path, "/audio/transcriptions");
+    let fields = requests[0].multipart().unwrap();
+    let field = |name: &str| fields.iter().find(|field| field.name == name).unwrap();
+    assert_eq!(field("file").file_name.as_deref(), Some("hello.mp3"));
+    assert_eq!(field("file").data, Bytes::from_static(b"ID3 audio"));
+    assert_eq!(field("model").text(), "whisper-1");
+    assert_eq!(field("language").text(), "en");
+    assert_eq!(field("response_format").text(), "json");
+}
+
 #[tokio::test]
 async fn translate_test() {
-    let client = Client::new();
+    let server = MockServer::start().await;
+    let client = server.client();
 
     let request = CreateTranslationRequestArgs::default().build().unwrap();
 
     let response = client.audio().translate(request).await;
 
     assert_err!(response); // FileReadError("cannot extract file name from ")
+    assert!(server.requests().is_empty());
 }
 
 #[tokio::test]
 async fn translate_sendable_test() {
-    let client = Client::new();
+    let server = MockServer::start().await;
+    let client = server.client();
 
     // https://github.com/64bit/async-openai/issues/140
     let translate = tokio::spawn(async move {
@@ -55,3 +99,33 @@ async fn translate_sendable_test() {
 
     assert_err!(response); // FileReadError("cannot extract file name from ")
 }
+
+#[tokio::test]
+async fn translate_multipart_test() {
+    let server = MockServer::start().await;
+    server.mock(
+        "POST",
+        "/audio/translations",
+        MockResponse::json(&json!({"text": "Hello world"})),
+    );
+    let client = server.client();
+
+    let request = CreateTranslationRequestArgs::default()
+        .file(AudioInput::from_vec_u8(
+            "bonjour.mp3".into(),
+            b"ID3 audio".to_vec(),
+        ))
+        .model("whisper-1")
+        .prompt("Greetings")
+        .build()
+        .unwrap();
+
+    let response = client.audio().translate(request).await.unwrap();
+    assert_eq!(response.text, "Hello world");
+
+    let fields = server.requests()[0].multipart().unwrap();
+    let names: Vec<_> = fields.iter().map(|field| field.name.as_str()).collect();
+    assert_eq!(names, ["file", "model", "prompt"]);
+    assert_eq!(fields[0].file_name.as_deref(), Some("bonjour.mp3"));
+    assert_eq!(fields[2].text(), "Greetings");
+}
@@ -1,12 +1,12 @@
 use async_openai::{
-    types::{CreateMessageRequestArgs, CreateRunRequestArgs, CreateThreadRequestArgs, RunStatus, MessageContent, CreateAssistantRequestArgs},
-    Client,
+    types::{CreateMessageRequestArgs, ListMessagesQueryArgs, CreateRunRequestArgs, CreateThreadRequestArgs, RunStatus, MessageContent, CreateAssistantRequestArgs},
+    Client, PollOptions,
 };
 use std::error::Error;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let query = [("limit", "1")]; //limit the list responses to 1 message
+    let query = ListMessagesQueryArgs::default().limit(1).build()?; //limit the list responses to 1 message
     
     //create a client
     let client = Client::new();
@@ -71,78 +71,55 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .create(run_request)
             .await?;
 
-        //wait for the run to complete
-        let mut awaiting_response = true;
-        while awaiting_response {
-            //retrieve the run
-            let run = client
-                .threads()
-                .runs(&thread.id)
-                .retrieve(&run.id)
-                .await?;
-            //check the status of the run
-            match run.status {
-                RunStatus::Completed => {
-                    awaiting_response = false;
-                    // once the run is completed we
-                    // get the response from the run
-                    // which will be the first message
-                    // in the thread
+        //wait for the run to finish
+        let run = client
+            .threads()
+            .runs(&thread.id)
+            .poll_until_terminal(&run.id, PollOptions::default())
+            .await?;
+        //check the status of the run
+        match run.status {
+            RunStatus::Completed => {
+                // once the run is completed we
+                // get the response from the run
+                // which will be the first message
+                // in the thread
 
-                    //retrieve the response from the run
-                    let response = client
-                        .threads()
-                        .messages(&thread.id)
-                        .list(&query)
-                        .await?;
-                    //get the message id from the response
-                    let message_id = response
-                        .data.get(0).unwrap()
-                        .id.clone();
-                    //get the message from the response
-                    let message = client
-                        .threads()
-                        .messages(&thread.id)
-                        .retrieve(&message_id)
-                        .await?;
-                    //get the content from the message
-                    let content = message
-                        .content.get(0).unwrap();
-                    //get the text from the content
-                    let text = match content {
-                        MessageContent::Text(text) => text.text.value.clone(),
-                        MessageContent::ImageFile(_) => panic!("imaged are not supported in the terminal"),
-                    };
-                    //print the text
-                    println!("--- Response: {}", text);
-                    println!("");
+                //retrieve the response from the run
+                let response = client
+                    .threads()
+                    .messages(&thread.id)
+                    .list(&query)
+                    .await?;
+                //get the message id from the response
+                let message_id = response
+                    .data.get(0).unwrap()
+                    .id.clone();
+                //get the message from the response
+                let message = client
+                    .threads()
+                    .messages(&thread.id)
+                    .retrieve(&message_id)
+                    .await?;
+                //get the content from the message
+                let content = message
+                    .content.get(0).unwrap();
+                //get the text from the content
+                let text = match content {
+                    MessageContent::Text(text) => text.text.value.clone(),
+                    MessageContent::ImageFile(_) => panic!("imaged are not supported in the terminal"),
+                };
+                //print the text
+                println!("--- Response: {}", text);
+                println!("");
 
-                }
-                RunStatus::Failed => {
-                    awaiting_response = false;
-                    println!("--- Run Failed: {:#?}", run);
-                }
-                RunStatus::Queued => {
-                    println!("--- Run Queued");
-                },
-                RunStatus::Cancelling => {
-                    println!("--- Run Cancelling");
-                },
-                RunStatus::Cancelled => {
-                    println!("--- Run Cancelled");
-                },
-                RunStatus::Expired => {
-                    println!("--- Run Expired");
-                },
-                RunStatus::RequiresAction => {
-                    println!("--- Run Requires Action");
-                },
-                RunStatus::InProgress => {
-                    println!("--- Waiting for response...");
-                }
             }
-            //wait for 1 second before checking the status again
-            std::thread::sleep(std::time::Duration::from_secs(1));
+            RunStatus::Failed => {
+                println!("--- Run Failed: {:#?}", run);
+            }
+            status => {
+                println!("--- Run {:?}", status);
+            }
         }
     }
 
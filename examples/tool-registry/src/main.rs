@@ -0,0 +1,69 @@
+use std::error::Error;
+
+use async_openai::{
+    tools::ToolRegistry,
+    types::{ChatCompletionRequestUserMessageArgs, CreateChatCompletionRequestArgs},
+    Client,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    Celsius,
+    Fahrenheit,
+}
+
+/// Get the current weather in a given location
+#[derive(Deserialize, JsonSchema)]
+struct GetCurrentWeather {
+    /// The city and state, e.g. San Francisco, CA
+    location: String,
+    unit: Option<Unit>,
+}
+
+async fn get_current_weather(args: GetCurrentWeather) -> Result<serde_json::Value, String> {
+    let (temperature, unit) = match args.unit.unwrap_or(Unit::Fahrenheit) {
+        Unit::Celsius => (22, "celsius"),
+        Unit::Fahrenheit => (72, "fahrenheit"),
+    };
+
+    Ok(json!({
+        "location": args.location,
+        "temperature": temperature,
+        "unit": unit,
+        "forecast": ["sunny", "windy"],
+    }))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+
+    let tools = ToolRegistry::new().with_function("get_current_weather", get_current_weather);
+
+    let request = CreateChatCompletionRequestArgs::default()
+        .max_tokens(512u16)
+        .model("gpt-3.5-turbo-1106")
+        .messages([ChatCompletionRequestUserMessageArgs::default()
+            .content("What's the weather like in Boston and Atlanta?")
+            .build()?
+            .into()])
+        .build()?;
+
+    // Tool calls of the model run concurrently, their results are sent back
+    // until the model answers, or 5 responses were received.
+    let result = client.chat().create_with_tools(request, &tools, 5).await?;
+
+    println!("\nResponse:\n");
+    for choice in result.response.choices {
+        println!(
+            "{}: Role: {}  Content: {:?}",
+            choice.index, choice.message.role, choice.message.content
+        );
+    }
+
+    Ok(())
+}